@@ -0,0 +1,47 @@
+//! Times moves on a 1000x1000 board. Run with `cargo bench`.
+use rustsweeper::{Board, Cell, CellPos, Game, Settings};
+use std::time::{Duration, Instant};
+
+const SIZE: i32 = 1000;
+const RUNS: u32 = 10;
+
+fn main() {
+    // No mines at all: one click uncovers every cell.
+    bench("reveal, whole board cascades", || {
+        let board = Board::new(SIZE as usize, SIZE as usize, Cell::new('0'));
+        Game::from_board(board, Settings::new(SIZE, SIZE, 0))
+    });
+    // Sparse mines: the first click places them and opens most of the board.
+    bench("first reveal, 10000 mines", || {
+        let mut settings = Settings::new(SIZE, SIZE, 10_000);
+        settings.set_seed(Some(1));
+        Game::new(settings)
+    });
+    bench("first reveal, 100000 mines", || {
+        let mut settings = Settings::new(SIZE, SIZE, 100_000);
+        settings.set_seed(Some(1));
+        Game::new(settings)
+    });
+}
+
+/// Reveals the middle cell of a fresh game from `setup` and prints the average time.
+/// Setting up the game is not timed.
+fn bench(name: &str, setup: impl Fn() -> Game) {
+    let mut total = Duration::ZERO;
+    let mut revealed = 0;
+    for _ in 0..RUNS {
+        let mut game = setup();
+        let start = Instant::now();
+        revealed = game
+            .reveal(CellPos {
+                x: SIZE / 2,
+                y: SIZE / 2,
+            })
+            .len();
+        total += start.elapsed();
+    }
+    println!(
+        "{name}: {:.2} ms ({revealed} cells revealed)",
+        total.as_secs_f64() * 1000.0 / RUNS as f64
+    );
+}
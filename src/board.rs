@@ -0,0 +1,115 @@
+use crate::Cell;
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::{Index, IndexMut},
+    slice::{Chunks, ChunksMut, Iter, IterMut},
+};
+
+/// The cells of a board, row after row in one vector.
+///
+/// Indexing with a row number gives that row as a slice, so a cell is `board[y][x]`.
+/// Saved as a list of rows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "Vec<Vec<Cell>>", try_from = "Vec<Vec<Cell>>")]
+pub struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+impl Board {
+    pub fn new(width: usize, height: usize, cell: Cell) -> Self {
+        Board {
+            width,
+            height,
+            cells: vec![cell; width * height],
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn rows(&self) -> Chunks<'_, Cell> {
+        self.cells.chunks(self.width.max(1))
+    }
+    pub fn rows_mut(&mut self) -> ChunksMut<'_, Cell> {
+        self.cells.chunks_mut(self.width.max(1))
+    }
+    /// Every cell, row after row.
+    pub fn cells(&self) -> Iter<'_, Cell> {
+        self.cells.iter()
+    }
+    pub fn cells_mut(&mut self) -> IterMut<'_, Cell> {
+        self.cells.iter_mut()
+    }
+    /// The position of a cell in [`cells`](Board::cells).
+    pub fn index_of(&self, y: usize, x: usize) -> usize {
+        y * self.width + x
+    }
+    /// The row and column of each cell touching `(y, x)`, not counting itself.
+    pub fn neighbours(&self, y: usize, x: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        (y.saturating_sub(1)..(y + 2).min(height))
+            .flat_map(move |around_y| {
+                (x.saturating_sub(1)..(x + 2).min(width)).map(move |around_x| (around_y, around_x))
+            })
+            .filter(move |around| *around != (y, x))
+    }
+}
+impl Index<usize> for Board {
+    type Output = [Cell];
+    fn index(&self, y: usize) -> &[Cell] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+impl IndexMut<usize> for Board {
+    fn index_mut(&mut self, y: usize) -> &mut [Cell] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+impl From<Board> for Vec<Vec<Cell>> {
+    fn from(board: Board) -> Self {
+        board.rows().map(<[Cell]>::to_vec).collect()
+    }
+}
+impl TryFrom<Vec<Vec<Cell>>> for Board {
+    type Error = String;
+    fn try_from(rows: Vec<Vec<Cell>>) -> Result<Self, Self::Error> {
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err("the board has no cells".to_owned());
+        }
+        if let Some(row) = rows.iter().position(|row| row.len() != width) {
+            return Err(format!(
+                "row {} has {} cells, expected {width}",
+                row + 1,
+                rows[row].len()
+            ));
+        }
+        Ok(Board {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+}
+
+/// One bit per cell, for marking cells already visited.
+pub(crate) struct BitSet {
+    words: Vec<u64>,
+}
+impl BitSet {
+    pub(crate) fn new(len: usize) -> Self {
+        BitSet {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+    /// Sets the bit at `index`. Returns false if it was already set.
+    pub(crate) fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        let was_set = self.words[word] & bit != 0;
+        self.words[word] |= bit;
+        !was_set
+    }
+}
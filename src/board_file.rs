@@ -0,0 +1,246 @@
+//! Boards as plain text, one line per row:
+//!
+//! - `.` a hidden safe cell and `*` a hidden mine
+//! - `0` to `8` a revealed safe cell (the number is worked out again on load)
+//! - `F` a flagged mine and `f` a flag on a safe cell
+//!
+//! Lines starting with `#` are comments. Every row must be the same length.
+//!
+//! Boards from other programs can be read too: MBF files (see [`parse_mbf`]) and the
+//! board of Viennasweeper RAW videos (see [`parse_rawvf`]). Arbiter's AVF videos are not
+//! supported, as that format has never been published.
+use crate::{
+    game::{place_numbers, Game},
+    Board, Cell, Mark, Settings,
+};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+/// Reads a board in the text format, with its numbers placed.
+pub fn parse_board(text: &str) -> io::Result<Board> {
+    let invalid = |message: String| io::Error::new(ErrorKind::InvalidData, message);
+    let mut rows: Vec<Vec<Cell>> = vec![];
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut row = vec![];
+        for character in line.chars() {
+            let mut cell = Cell::new('0');
+            match character {
+                '.' => {}
+                '*' => cell.element = 'M',
+                '0'..='8' => cell.hidden = false,
+                'F' => {
+                    cell.element = 'M';
+                    cell.mark = Mark::Flag;
+                }
+                'f' => cell.mark = Mark::Flag,
+                _ => {
+                    return Err(invalid(format!(
+                        "line {}: unknown cell {character:?}",
+                        line_number + 1
+                    )))
+                }
+            }
+            row.push(cell);
+        }
+        if rows.first().is_some_and(|first| first.len() != row.len()) {
+            return Err(invalid(format!(
+                "line {}: expected {} cells, found {}",
+                line_number + 1,
+                rows[0].len(),
+                row.len()
+            )));
+        }
+        rows.push(row);
+    }
+    if rows.is_empty() {
+        return Err(invalid("the board has no rows".to_owned()));
+    }
+    let mut board = Board::try_from(rows).map_err(invalid)?;
+    place_numbers(&mut board);
+    Ok(board)
+}
+
+/// Reads an MBF board: one byte each for width and height, the mine count as a big-endian
+/// 16-bit number, then one `x`, `y` byte pair per mine.
+pub fn parse_mbf(bytes: &[u8]) -> io::Result<Board> {
+    let invalid = |message: String| io::Error::new(ErrorKind::InvalidData, message);
+    let [width, height, high, low, mines @ ..] = bytes else {
+        return Err(invalid("the MBF header is cut short".to_owned()));
+    };
+    let (width, height) = (*width as usize, *height as usize);
+    let mine_count = u16::from_be_bytes([*high, *low]) as usize;
+    if width == 0 || height == 0 {
+        return Err(invalid(format!("the board is {width}x{height}")));
+    }
+    if mines.len() < mine_count * 2 {
+        return Err(invalid(format!(
+            "expected {mine_count} mines, found {}",
+            mines.len() / 2
+        )));
+    }
+    let mut board = Board::new(width, height, Cell::new('0'));
+    for pair in mines.chunks_exact(2).take(mine_count) {
+        let (x, y) = (pair[0] as usize, pair[1] as usize);
+        if x >= width || y >= height {
+            return Err(invalid(format!("mine at {x}, {y} is outside the board")));
+        }
+        if board[y][x].element == 'M' {
+            return Err(invalid(format!("mine at {x}, {y} is listed twice")));
+        }
+        board[y][x].element = 'M';
+    }
+    place_numbers(&mut board);
+    Ok(board)
+}
+
+/// Reads the board of a Viennasweeper RAW video (`.rawvf`). Only the `Width`, `Height`
+/// and `Mines` header lines and the `Board:` section are used; `*` marks a mine and
+/// any other character a safe cell. The recorded mouse events are ignored.
+pub fn parse_rawvf(text: &str) -> io::Result<Board> {
+    let invalid = |message: String| io::Error::new(ErrorKind::InvalidData, message);
+    let header = |name: &str| -> io::Result<usize> {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .ok_or_else(|| invalid(format!("the {name} line is missing")))?
+            .trim()
+            .parse()
+            .map_err(|_| invalid(format!("the {name} line is not a number")))
+    };
+    let (width, height, mines) = (header("Width")?, header("Height")?, header("Mines")?);
+    if width == 0 || height == 0 {
+        return Err(invalid(format!("the board is {width}x{height}")));
+    }
+    let rows: Vec<&str> = text
+        .lines()
+        .skip_while(|line| line.trim() != "Board:")
+        .skip(1)
+        .take(height)
+        .collect();
+    if rows.len() != height || rows.iter().any(|row| row.trim().chars().count() != width) {
+        return Err(invalid(format!(
+            "the board section is not {width}x{height}"
+        )));
+    }
+    let mut board = Board::new(width, height, Cell::new('0'));
+    for (y, row) in rows.iter().enumerate() {
+        for (x, character) in row.trim().chars().enumerate() {
+            if character == '*' {
+                board[y][x].element = 'M';
+            }
+        }
+    }
+    let found = board.cells().filter(|c| c.element == 'M').count();
+    if found != mines {
+        return Err(invalid(format!("expected {mines} mines, found {found}")));
+    }
+    place_numbers(&mut board);
+    Ok(board)
+}
+
+/// Writes a board in the text format. Mines are always written as hidden.
+pub fn dump_board(board: &Board) -> String {
+    let mut text = String::new();
+    for row in board.rows() {
+        for cell in row {
+            text.push(match (cell.element, cell.hidden, cell.is_flagged()) {
+                ('M', _, true) => 'F',
+                ('M', _, false) => '*',
+                (_, true, true) => 'f',
+                (_, true, false) => '.',
+                (number, false, _) => number,
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Starts a game on the board in `path`. The size and mine count come from the file,
+/// everything else from `settings`. `.mbf` and `.rawvf` files are read in those
+/// formats, anything else as the text format.
+pub fn load_board_file(path: &Path, mut settings: Settings) -> io::Result<Game> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    let board = match extension.as_deref() {
+        Some("mbf") => fs::read(path).and_then(|bytes| parse_mbf(&bytes)),
+        Some("rawvf") => fs::read_to_string(path).and_then(|text| parse_rawvf(&text)),
+        _ => fs::read_to_string(path).and_then(|text| parse_board(&text)),
+    }
+    .map_err(|error| io::Error::new(error.kind(), format!("{}: {error}", path.display())))?;
+    settings.width = board.width() as i32;
+    settings.height = board.height() as i32;
+    settings.mines = board.cells().filter(|c| c.element == 'M').count() as i32;
+    settings.board_file = true;
+    Ok(Game::from_board(board, settings))
+}
+
+pub fn save_board_file(path: &Path, board: &Board) -> io::Result<()> {
+    fs::write(path, dump_board(board))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_board_reads_every_cell_kind() {
+        let board = parse_board("# a comment\n.*f\n1F.\n").unwrap();
+        assert_eq!((board.width(), board.height()), (3, 2));
+        assert_eq!(board[0][1].element, 'M');
+        assert!(board[0][2].is_flagged() && board[0][2].element != 'M');
+        assert!(!board[1][0].hidden);
+        assert_eq!(board[1][0].element, '2');
+        assert!(board[1][1].is_flagged() && board[1][1].element == 'M');
+        assert_eq!(dump_board(&board), ".*f\n2F.\n");
+    }
+
+    #[test]
+    fn parse_board_rejects_bad_boards() {
+        assert!(parse_board("..\n...\n").is_err());
+        assert!(parse_board(".x\n").is_err());
+        assert!(parse_board("# only a comment\n").is_err());
+    }
+
+    #[test]
+    fn parse_mbf_places_the_listed_mines() {
+        let board = parse_mbf(&[3, 2, 0, 2, 0, 0, 2, 1]).unwrap();
+        assert_eq!((board.width(), board.height()), (3, 2));
+        assert_eq!(dump_board(&board), "*..\n..*\n");
+        assert_eq!(board[0][1].element, '2');
+    }
+
+    #[test]
+    fn parse_mbf_rejects_bad_files() {
+        assert!(parse_mbf(&[3, 2, 0]).is_err());
+        assert!(parse_mbf(&[0, 2, 0, 0]).is_err());
+        assert!(parse_mbf(&[3, 2, 0, 2, 0, 0]).is_err());
+        assert!(parse_mbf(&[3, 2, 0, 1, 3, 0]).is_err());
+        assert!(parse_mbf(&[3, 2, 0, 2, 1, 1, 1, 1]).is_err());
+    }
+
+    #[test]
+    fn parse_rawvf_reads_the_board_section() {
+        let text =
+            "RawVF_Version: Rev5\nWidth: 3\nHeight: 2\nMines: 2\nBoard:\n*..\n..*\nEvents:\n";
+        let board = parse_rawvf(text).unwrap();
+        assert_eq!(dump_board(&board), "*..\n..*\n");
+    }
+
+    #[test]
+    fn parse_rawvf_rejects_bad_videos() {
+        let error = parse_rawvf("Width: 0\nHeight: 0\nMines: 0\nBoard:\n").unwrap_err();
+        assert_eq!(error.to_string(), "the board is 0x0");
+        assert!(parse_rawvf("Width: 3\nHeight: 1\nMines: 1\nBoard:\n*.\n").is_err());
+        assert!(parse_rawvf("Width: 2\nHeight: 1\nMines: 2\nBoard:\n*.\n").is_err());
+        assert!(parse_rawvf("Width: 2\nMines: 1\nBoard:\n*.\n").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{load_toml, save_toml, Settings};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+/// Bumped whenever the layout of the config file changes in a way old versions can't read.
+const CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    version: u32,
+    settings: Settings,
+}
+
+impl Settings {
+    /// Reads `config.toml` from the user's config directory. A missing file gives the
+    /// default settings.
+    pub fn load() -> io::Result<Settings> {
+        let path = config_path()?;
+        let Some(config) = load_toml::<Config>(&path)? else {
+            return Ok(Settings::default());
+        };
+        if config.version > CONFIG_VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} is version {}, this rustsweeper only reads up to version {CONFIG_VERSION}",
+                    path.display(),
+                    config.version
+                ),
+            ));
+        }
+        let mut settings = config.settings;
+        // Checked like the "Custom" difficulty menu, so the size stays within the limits.
+        let size = (
+            u32::try_from(settings.width),
+            u32::try_from(settings.height),
+            u32::try_from(settings.mines),
+        );
+        let checked = match size {
+            (Ok(width), Ok(height), Ok(mines)) => settings.set_size(width, height, mines),
+            _ => Err("Board size cannot be negative".to_owned()),
+        };
+        checked.map_err(|message| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has an invalid board size: {message}", path.display()),
+            )
+        })?;
+        // Without these a game could not be played or left.
+        if let Some(action) = settings.key_bindings.first_unbound() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} binds no key to {}", path.display(), action.name()),
+            ));
+        }
+        Ok(settings)
+    }
+    /// Writes the settings to `config.toml`, so they are used again next time.
+    pub fn save(&self) -> io::Result<()> {
+        let config = Config {
+            version: CONFIG_VERSION,
+            settings: *self,
+        };
+        save_toml(&config_path()?, &config)
+    }
+}
+
+fn config_path() -> io::Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("rustsweeper").join("config.toml"))
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no config directory found"))
+}
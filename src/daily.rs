@@ -0,0 +1,116 @@
+use crate::{data_path, game::Game, load_toml, save_toml, CellPos, GameStatus, Metrics, Settings};
+use chrono::{Datelike, Local, NaiveDate};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io};
+
+const DAILY_FILE: &str = "daily.toml";
+
+/// The board of the day. Everything about it comes from the date, so every player gets
+/// the same board on the same day without going online.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Daily {
+    date: NaiveDate,
+}
+impl Daily {
+    /// Today's board, by the local date.
+    pub fn today() -> Self {
+        Daily::new(Local::now().date_naive())
+    }
+    pub fn new(date: NaiveDate) -> Self {
+        Daily { date }
+    }
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+    /// `base` with the size, mines and seed of this day's board. No-guess generation is
+    /// turned off, as it depends on how fast the computer is.
+    pub fn settings(&self, base: Settings) -> Settings {
+        let mut rng = self.rng();
+        let mut settings = base;
+        settings.width = rng.gen_range(9..=30);
+        settings.height = rng.gen_range(9..=16);
+        settings.mines = settings.width * settings.height * rng.gen_range(12..=20) / 100;
+        settings.seed = Some(rng.gen());
+        settings.no_guess = false;
+        settings.daily = Some(self.date);
+        settings
+    }
+    /// A new game on this day's board. Mines are placed around the first click, so the
+    /// game makes that click itself on a cell picked from the date.
+    pub fn game(&self, base: Settings) -> Game {
+        let settings = self.settings(base);
+        let mut rng = self.rng();
+        let start = CellPos {
+            x: rng.gen_range(0..settings.width),
+            y: rng.gen_range(0..settings.height),
+        };
+        let mut game = Game::new(settings);
+        game.reveal(start);
+        // The first click is the day's, not the player's.
+        game.begin_here();
+        game
+    }
+    /// A fresh generator for the things picked from the date: size and seed first, then
+    /// the starting cell.
+    fn rng(&self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.date.num_days_from_ce() as u64)
+    }
+}
+
+/// The first attempt at one day's board.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyResult {
+    /// How many times the board was started that day. Only the first counts.
+    pub attempts: u32,
+    /// False while the counted attempt is still going, or if it was left unfinished.
+    pub finished: bool,
+    /// Only a win without undo or hints counts.
+    pub won: bool,
+    /// Whether undo or hints were used, which keeps the day from counting as won.
+    #[serde(default)]
+    pub practice: bool,
+    pub time_ms: u64,
+    pub three_bv: u32,
+    pub three_bv_per_second: f64,
+}
+
+/// Every day's result, stored in `daily.toml` under the user's data directory and keyed
+/// by date (`YYYY-MM-DD`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyHistory {
+    pub days: BTreeMap<String, DailyResult>,
+}
+impl DailyHistory {
+    /// Reads the history file. A missing file gives an empty history.
+    pub fn load() -> io::Result<DailyHistory> {
+        Ok(load_toml(&data_path(DAILY_FILE)?)?.unwrap_or_default())
+    }
+    pub fn save(&self) -> io::Result<()> {
+        save_toml(&data_path(DAILY_FILE)?, self)
+    }
+    /// Notes that the board of `date` was started. Returns true for the first attempt,
+    /// the only one that counts.
+    pub fn start(&mut self, date: NaiveDate) -> bool {
+        let day = self.days.entry(date.to_string()).or_default();
+        day.attempts += 1;
+        day.attempts == 1
+    }
+    /// Fills in the result of the counted attempt at the board of `date`. Returns false
+    /// when `game` is not that attempt.
+    pub fn finish(&mut self, date: NaiveDate, game: &Game, metrics: &Metrics) -> bool {
+        match self.days.get_mut(&date.to_string()) {
+            Some(day) if day.attempts == 1 && !day.finished => {
+                day.finished = true;
+                day.practice = game.is_practice() || game.hints_used() > 0;
+                day.won = game.status() == GameStatus::Won && !day.practice;
+                day.time_ms = game.elapsed().as_millis() as u64;
+                day.three_bv = metrics.three_bv;
+                day.three_bv_per_second = metrics.three_bv_per_second();
+                true
+            }
+            _ => false,
+        }
+    }
+}
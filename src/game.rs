@@ -0,0 +1,854 @@
+use crate::{
+    board::BitSet,
+    solver::{self, Hint},
+    Board, Settings,
+};
+use rand::{prelude::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How long no-guess generation may search before falling back to a regular board.
+pub const NO_GUESS_TIME_LIMIT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Cell {
+    pub(crate) hidden: bool,
+    pub(crate) element: char,
+    pub(crate) mark: Mark,
+    #[serde(skip)]
+    pub(crate) selected: bool,
+}
+impl Cell {
+    pub fn new(element: char) -> Self {
+        Cell {
+            hidden: true,
+            element,
+            mark: Mark::None,
+            selected: false,
+        }
+    }
+    pub fn element(&self) -> char {
+        self.element
+    }
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+    pub fn is_flagged(&self) -> bool {
+        self.mark == Mark::Flag
+    }
+    pub fn mark(&self) -> Mark {
+        self.mark
+    }
+}
+
+/// What the player has written on a hidden cell. Only flags count as mines; question
+/// marks are notes and are treated like unmarked cells by the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Mark {
+    None,
+    Flag,
+    Question,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CellPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    NotStarted,
+    Playing,
+    Won,
+    Lost,
+}
+
+/// One input from the player, as kept for replays.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Move {
+    /// Milliseconds since the game was created.
+    pub time_ms: u64,
+    pub kind: MoveKind,
+    pub pos: CellPos,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MoveKind {
+    Reveal,
+    Chord,
+    Flag,
+    /// The selected cell moved. Changes nothing on the board.
+    Cursor,
+    /// The last move was taken back. `pos` is not used.
+    Undo,
+    /// The last undone move was made again. `pos` is not used.
+    Redo,
+}
+
+/// The cells a game began with already uncovered or flagged, as in puzzles.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StartState {
+    pub revealed: Vec<CellPos>,
+    pub flagged: Vec<CellPos>,
+}
+impl StartState {
+    fn of(board: &Board) -> Self {
+        let mut start = StartState::default();
+        for (y, row) in board.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let pos = CellPos {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                if !cell.hidden {
+                    start.revealed.push(pos);
+                }
+                if cell.is_flagged() {
+                    start.flagged.push(pos);
+                }
+            }
+        }
+        start
+    }
+    /// Uncovers and flags these cells on `board`.
+    pub fn apply(&self, board: &mut Board) {
+        for pos in self.revealed.iter() {
+            board[pos.y as usize][pos.x as usize].hidden = false;
+        }
+        for pos in self.flagged.iter() {
+            board[pos.y as usize][pos.x as usize].mark = Mark::Flag;
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Click {
+    Dead,
+    Fine,
+}
+
+/// Whether a cell is hidden and how it is marked: the parts of it a move can change.
+type CellState = (bool, Mark);
+
+/// What one move did to the board, so that it can be undone and redone.
+#[derive(Debug, Clone)]
+struct Step {
+    /// Index of each changed cell with its `hidden` and mark before and after.
+    cells: Vec<(usize, CellState, CellState)>,
+    status: (GameStatus, GameStatus),
+    revealed: (usize, usize),
+}
+
+/// The rules of a single game, without any terminal I/O.
+///
+/// Every move returns the cells it changed so a renderer only has to redraw those.
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub(crate) board: Board,
+    settings: Settings,
+    /// Safe cells uncovered so far. The game is won once this reaches `safe_cells`.
+    revealed: usize,
+    safe_cells: usize,
+    status: GameStatus,
+    seed: u64,
+    no_guess_fallback: bool,
+    hints_used: u32,
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+    created_at: Instant,
+    moves: Vec<Move>,
+    start: StartState,
+    /// Cells the move in progress has changed, with their state from before it.
+    pending: Vec<(usize, CellState)>,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    practice: bool,
+}
+impl Game {
+    /// Creates an empty board. Mines are placed on the first reveal, away from that cell.
+    ///
+    /// The layout comes from `settings.seed` (or a random seed when unset) alone. The first
+    /// click only moves the mines around it elsewhere, so boards with the same seed differ at
+    /// most around where each player started.
+    pub fn new(settings: Settings) -> Self {
+        Game {
+            board: Board::new(
+                settings.width as usize,
+                settings.height as usize,
+                Cell::new('0'),
+            ),
+            settings,
+            revealed: 0,
+            safe_cells: 0,
+            status: GameStatus::NotStarted,
+            seed: settings.seed.unwrap_or_else(rand::random),
+            no_guess_fallback: false,
+            hints_used: 0,
+            started_at: None,
+            finished_at: None,
+            created_at: Instant::now(),
+            moves: vec![],
+            start: StartState::default(),
+            pending: vec![],
+            undo: vec![],
+            redo: vec![],
+            practice: false,
+        }
+    }
+    /// Creates a game from a board that already has its mines placed. Cells already
+    /// uncovered or flagged on it are kept as the [`start`](Game::start) of the game.
+    pub fn from_board(mut board: Board, settings: Settings) -> Self {
+        place_numbers(&mut board);
+        let (revealed, safe_cells) = count_safe_cells(&board);
+        let start = StartState::of(&board);
+        let mut game = Game {
+            board,
+            settings,
+            revealed,
+            safe_cells,
+            status: GameStatus::Playing,
+            seed: settings.seed.unwrap_or_default(),
+            no_guess_fallback: false,
+            hints_used: 0,
+            started_at: None,
+            finished_at: None,
+            created_at: Instant::now(),
+            moves: vec![],
+            start,
+            pending: vec![],
+            undo: vec![],
+            redo: vec![],
+            practice: false,
+        };
+        if game.revealed == game.safe_cells {
+            game.status = GameStatus::Won;
+        }
+        game
+    }
+    /// Puts back a game saved part way through, with its clock already at `elapsed`.
+    /// New moves are timed on from the last of `moves`. Nothing can be undone from before
+    /// the restore.
+    pub(crate) fn restore(
+        board: Board,
+        settings: Settings,
+        seed: u64,
+        hints_used: u32,
+        elapsed: Duration,
+        moves: Vec<Move>,
+        practice: bool,
+    ) -> Self {
+        let (revealed, safe_cells) = count_safe_cells(&board);
+        let recorded = Duration::from_millis(moves.last().map_or(0, |last| last.time_ms));
+        Game {
+            board,
+            settings,
+            revealed,
+            safe_cells,
+            status: GameStatus::Playing,
+            seed,
+            no_guess_fallback: false,
+            hints_used,
+            // A board saved before its first move starts its clock with the next one.
+            started_at: moves
+                .iter()
+                .any(|mv| mv.kind != MoveKind::Cursor)
+                .then(|| Instant::now().checked_sub(elapsed))
+                .flatten(),
+            finished_at: None,
+            created_at: Instant::now()
+                .checked_sub(recorded)
+                .unwrap_or_else(Instant::now),
+            moves,
+            start: StartState::default(),
+            pending: vec![],
+            undo: vec![],
+            redo: vec![],
+            practice,
+        }
+    }
+    /// Sets what the board looked like before the first move, for a restored game.
+    pub(crate) fn with_start(mut self, start: StartState) -> Self {
+        self.start = start;
+        self
+    }
+    /// Makes the board as it is now the [`start`](Game::start) of the game, for moves made
+    /// on the player's behalf. They are dropped from the moves and can't be undone, and
+    /// the clock waits for the player's first move.
+    pub(crate) fn begin_here(&mut self) {
+        self.start = StartState::of(&self.board);
+        self.moves.clear();
+        self.undo.clear();
+        self.redo.clear();
+        self.started_at = None;
+        self.created_at = Instant::now();
+    }
+    /// A new game on the same board, back where it was before the first move.
+    pub fn restart(&self) -> Game {
+        let mut board = self.board.clone();
+        for cell in board.cells_mut() {
+            cell.hidden = true;
+            cell.mark = Mark::None;
+            cell.selected = false;
+        }
+        self.start.apply(&mut board);
+        Game::from_board(board, self.settings)
+    }
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// True when no-guess generation hit [`NO_GUESS_TIME_LIMIT`] and a regular board was used.
+    pub fn no_guess_fallback(&self) -> bool {
+        self.no_guess_fallback
+    }
+    pub fn hints_used(&self) -> u32 {
+        self.hints_used
+    }
+    /// True once a move has been undone. Practice games don't count towards best times.
+    pub fn is_practice(&self) -> bool {
+        self.practice
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    /// Every input made so far, oldest first.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+    /// The cells that were uncovered or flagged before the first move.
+    pub fn start(&self) -> &StartState {
+        &self.start
+    }
+    /// Number of cells currently marked with a flag.
+    pub fn flags(&self) -> usize {
+        self.board.cells().filter(|c| c.is_flagged()).count()
+    }
+    /// Time since the first click, stopped once the game is over.
+    pub fn elapsed(&self) -> Duration {
+        match (self.started_at, self.finished_at) {
+            (Some(started_at), Some(finished_at)) => finished_at - started_at,
+            (Some(started_at), None) => started_at.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+    /// Whether the clock is running: the first move was made and the game isn't over.
+    pub fn is_timing(&self) -> bool {
+        self.started_at.is_some() && self.finished_at.is_none()
+    }
+    /// Asks the solver for one certain move. Every hint given counts against this game.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if self.status != GameStatus::Playing {
+            return None;
+        }
+        let hint = solver::hint(&self.board, &self.settings);
+        if hint.is_some() {
+            self.hints_used += 1;
+        }
+        hint
+    }
+    pub fn reveal(&mut self, pos: CellPos) -> Vec<CellPos> {
+        let mut changed = vec![];
+        if !self.in_progress() || !self.contains(pos) {
+            return changed;
+        }
+        self.record(MoveKind::Reveal, pos);
+        let before = (self.status, self.revealed);
+        if self.status == GameStatus::NotStarted {
+            self.generate(pos);
+        }
+        let click = self.event(pos, &mut changed);
+        self.update_status(click);
+        self.push_step(before);
+        changed
+    }
+    /// Reveals every unflagged neighbour of a revealed number once the flags around it
+    /// add up to that number. A wrong flag means one of those neighbours is a mine.
+    pub fn chord(&mut self, pos: CellPos) -> Vec<CellPos> {
+        let mut changed = vec![];
+        if self.status != GameStatus::Playing || !self.contains(pos) {
+            return changed;
+        }
+        let cell = self.board[pos.y as usize][pos.x as usize];
+        if cell.hidden || cell.is_flagged() {
+            return changed;
+        }
+        let number = cell.element.to_digit(10).unwrap_or(0);
+        let around: Vec<(usize, usize)> = self
+            .board
+            .neighbours(pos.y as usize, pos.x as usize)
+            .collect();
+        let flagged = around
+            .iter()
+            .filter(|(y, x)| self.board[*y][*x].is_flagged())
+            .count();
+        if number == 0 || flagged != number as usize {
+            return changed;
+        }
+        // Only chords that can go ahead are kept, like flags.
+        self.record(MoveKind::Chord, pos);
+        let before = (self.status, self.revealed);
+        let mut click = Click::Fine;
+        for (y, x) in around {
+            let around_cell = self.board[y][x];
+            if around_cell.hidden && !around_cell.is_flagged() {
+                let around_pos = CellPos {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                if self.event(around_pos, &mut changed) == Click::Dead {
+                    click = Click::Dead;
+                }
+            }
+        }
+        self.update_status(click);
+        self.push_step(before);
+        changed
+    }
+    /// Cycles the mark on a cell: none, flag, then question mark when those are enabled.
+    pub fn toggle_flag(&mut self, pos: CellPos) -> Vec<CellPos> {
+        // Only hidden cells can be marked.
+        if !self.in_progress()
+            || !self.contains(pos)
+            || !self.board[pos.y as usize][pos.x as usize].hidden
+        {
+            return vec![];
+        }
+        self.record(MoveKind::Flag, pos);
+        let before = (self.status, self.revealed);
+        let index = self.board.index_of(pos.y as usize, pos.x as usize);
+        let cell = &mut self.board[pos.y as usize][pos.x as usize];
+        self.pending.push((index, (cell.hidden, cell.mark)));
+        cell.mark = match cell.mark {
+            Mark::None => Mark::Flag,
+            Mark::Flag if self.settings.question_marks => Mark::Question,
+            Mark::Flag | Mark::Question => Mark::None,
+        };
+        self.push_step(before);
+        vec![pos]
+    }
+    /// Takes back the last reveal, chord or flag, even one that lost the game, and marks
+    /// this game as practice. Returns the cells that changed, or `None` when there is
+    /// nothing to undo.
+    pub fn undo(&mut self) -> Option<Vec<CellPos>> {
+        let step = self.undo.pop()?;
+        self.record(MoveKind::Undo, CellPos { x: 0, y: 0 });
+        self.practice = true;
+        let changed = self.set_cells(
+            step.cells
+                .iter()
+                .map(|(index, before, _)| (*index, *before)),
+        );
+        // Mines stay where the first reveal put them, so the game never goes back to
+        // not being started.
+        self.status = match step.status.0 {
+            GameStatus::NotStarted => GameStatus::Playing,
+            status => status,
+        };
+        self.revealed = step.revealed.0;
+        self.finished_at = None;
+        self.redo.push(step);
+        Some(changed)
+    }
+    /// Makes the last undone move again. Returns `None` when there is nothing to redo.
+    pub fn redo(&mut self) -> Option<Vec<CellPos>> {
+        let step = self.redo.pop()?;
+        self.record(MoveKind::Redo, CellPos { x: 0, y: 0 });
+        let changed = self.set_cells(step.cells.iter().map(|(index, _, after)| (*index, *after)));
+        self.status = step.status.1;
+        self.revealed = step.revealed.1;
+        if !self.in_progress() {
+            self.finished_at = Some(Instant::now());
+        }
+        self.undo.push(step);
+        Some(changed)
+    }
+    /// Notes that the player moved the selection to `pos`, for replays.
+    pub(crate) fn record_cursor(&mut self, pos: CellPos) {
+        if self.in_progress() && self.contains(pos) {
+            self.record(MoveKind::Cursor, pos);
+        }
+    }
+    /// Plays back a recorded move.
+    pub fn apply(&mut self, mv: &Move) -> Vec<CellPos> {
+        match mv.kind {
+            MoveKind::Reveal => self.reveal(mv.pos),
+            MoveKind::Chord => self.chord(mv.pos),
+            MoveKind::Flag => self.toggle_flag(mv.pos),
+            MoveKind::Cursor => vec![],
+            MoveKind::Undo => self.undo().unwrap_or_default(),
+            MoveKind::Redo => self.redo().unwrap_or_default(),
+        }
+    }
+    /// Uncovers every cell, used to show the board once the game is over.
+    ///
+    /// The cells uncovered belong to the move that ended the game, so undoing that move
+    /// covers them again.
+    pub fn reveal_all(&mut self) -> Vec<CellPos> {
+        let mut changed = vec![];
+        let width = self.board.width();
+        for (index, cell) in self.board.cells_mut().enumerate() {
+            if cell.hidden {
+                self.pending.push((index, (cell.hidden, cell.mark)));
+            }
+            if cell.hidden || cell.selected {
+                changed.push(CellPos {
+                    x: (index % width) as i32,
+                    y: (index / width) as i32,
+                });
+            }
+            cell.hidden = false;
+            cell.selected = false;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let (game_over, status) = (!self.in_progress(), self.status);
+        match self.undo.last_mut() {
+            Some(step) if game_over && step.status.1 == status => {
+                step.cells.extend(
+                    pending
+                        .into_iter()
+                        .map(|(index, before)| (index, before, (false, before.1))),
+                );
+            }
+            _ => {}
+        }
+        changed
+    }
+    /// Places the mines for a first click at `pos`. In no-guess mode boards are regenerated
+    /// until the solver can clear them from that click, or until the time limit runs out.
+    fn generate(&mut self, pos: CellPos) {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let deadline = Instant::now() + NO_GUESS_TIME_LIMIT;
+        loop {
+            for cell in self.board.cells_mut() {
+                cell.element = '0';
+            }
+            place_mines(&mut self.board, &self.settings, pos, &mut rng);
+            place_numbers(&mut self.board);
+            (self.revealed, self.safe_cells) = count_safe_cells(&self.board);
+            self.status = GameStatus::Playing;
+            self.started_at = Some(Instant::now());
+            if !self.settings.no_guess {
+                break;
+            }
+            let mut trial = self.clone();
+            for cell in trial.board.cells_mut() {
+                cell.mark = Mark::None;
+            }
+            trial.reveal(pos);
+            if solver::solves(&mut trial, Some(deadline)) {
+                break;
+            }
+            if Instant::now() >= deadline {
+                self.no_guess_fallback = true;
+                break;
+            }
+        }
+    }
+    fn record(&mut self, kind: MoveKind, pos: CellPos) {
+        // Boards with their mines already placed are timed from the first move, not from
+        // when they were loaded.
+        if kind != MoveKind::Cursor && self.started_at.is_none() {
+            self.started_at = Some(Instant::now());
+        }
+        self.moves.push(Move {
+            time_ms: self.created_at.elapsed().as_millis() as u64,
+            kind,
+            pos,
+        });
+    }
+    /// Turns the cells changed by the move just made into an undo step. Moves that
+    /// changed nothing are left out.
+    fn push_step(&mut self, (status, revealed): (GameStatus, usize)) {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() && status == self.status {
+            return;
+        }
+        let cells = pending
+            .into_iter()
+            .map(|(index, before)| {
+                let cell = self.board[index / self.board.width()][index % self.board.width()];
+                (index, before, (cell.hidden, cell.mark))
+            })
+            .collect();
+        self.undo.push(Step {
+            cells,
+            status: (status, self.status),
+            revealed: (revealed, self.revealed),
+        });
+        self.redo.clear();
+    }
+    /// Sets `hidden` and the mark of each cell given by index. Returns their positions.
+    fn set_cells(&mut self, cells: impl Iterator<Item = (usize, CellState)>) -> Vec<CellPos> {
+        let width = self.board.width();
+        let mut changed = vec![];
+        for (index, (hidden, mark)) in cells {
+            let (y, x) = (index / width, index % width);
+            let cell = &mut self.board[y][x];
+            cell.hidden = hidden;
+            cell.mark = mark;
+            changed.push(CellPos {
+                x: x as i32,
+                y: y as i32,
+            });
+        }
+        changed
+    }
+    fn update_status(&mut self, click: Click) {
+        if click == Click::Dead {
+            self.status = GameStatus::Lost;
+        } else if self.revealed == self.safe_cells {
+            self.status = GameStatus::Won;
+        }
+        if !self.in_progress() && self.finished_at.is_none() {
+            self.finished_at = Some(Instant::now());
+        }
+    }
+    fn in_progress(&self) -> bool {
+        matches!(self.status, GameStatus::NotStarted | GameStatus::Playing)
+    }
+    fn contains(&self, pos: CellPos) -> bool {
+        pos.x >= 0 && pos.y >= 0 && pos.x < self.settings.width && pos.y < self.settings.height
+    }
+    fn unhide(&mut self, y: usize, x: usize, changed: &mut Vec<CellPos>) {
+        let index = self.board.index_of(y, x);
+        let cell = &mut self.board[y][x];
+        if !cell.hidden {
+            return;
+        }
+        self.pending.push((index, (cell.hidden, cell.mark)));
+        cell.hidden = false;
+        self.revealed += 1;
+        changed.push(CellPos {
+            x: x as i32,
+            y: y as i32,
+        });
+    }
+    /// Uncovers the area around an empty cell, spreading through every empty cell reached.
+    fn deobfuscate_board(&mut self, cell_pos: CellPos, changed: &mut Vec<CellPos>) {
+        let (y, x) = (cell_pos.y as usize, cell_pos.x as usize);
+        let mut checked = BitSet::new(self.board.cells().len());
+        checked.insert(self.board.index_of(y, x));
+        let mut to_check = vec![(y, x)];
+        while let Some((y, x)) = to_check.pop() {
+            for (around_y, around_x) in self.board.neighbours(y, x) {
+                if !checked.insert(self.board.index_of(around_y, around_x)) {
+                    continue;
+                }
+                let element = self.board[around_y][around_x].element;
+                if element == 'M' {
+                    continue;
+                }
+                self.unhide(around_y, around_x, changed);
+                if element == '0' {
+                    to_check.push((around_y, around_x));
+                }
+            }
+        }
+    }
+    fn event(&mut self, cell_pos: CellPos, changed: &mut Vec<CellPos>) -> Click {
+        let cell = self.board[cell_pos.y as usize][cell_pos.x as usize];
+        if cell.is_flagged() {
+            return Click::Fine;
+        }
+        let cell_type = cell.element;
+        if cell_type == 'M' {
+            Click::Dead
+        } else if cell_type != '0' {
+            self.unhide(cell_pos.y as usize, cell_pos.x as usize, changed);
+            Click::Fine
+        } else {
+            self.unhide(cell_pos.y as usize, cell_pos.x as usize, changed);
+            self.deobfuscate_board(cell_pos, changed);
+            Click::Fine
+        }
+    }
+}
+
+/// Shuffles every cell with `rng` and puts the mines on the first ones. Mines that land
+/// around the first click move to the next cells of the same order away from it. Boards
+/// too full for that only keep the clicked cell itself free.
+fn place_mines(
+    board: &mut Board,
+    settings: &Settings,
+    starting_coords: CellPos,
+    rng: &mut ChaCha8Rng,
+) {
+    let width = settings.width as usize;
+    let mut order: Vec<usize> = (0..board.cells().len()).collect();
+    order.shuffle(rng);
+    let start = board.index_of(starting_coords.y as usize, starting_coords.x as usize);
+    let near_start = |index: usize| {
+        (starting_coords.y - (index / width) as i32).abs() <= 1
+            && (starting_coords.x - (index % width) as i32).abs() <= 1
+    };
+    let (mines, rest) = order.split_at(settings.mines as usize);
+    let mut far = rest.iter().filter(|index| !near_start(**index));
+    for &index in mines {
+        let index = if near_start(index) {
+            far.next().copied().unwrap_or(index)
+        } else {
+            index
+        };
+        board[index / width][index % width].element = 'M';
+    }
+    if board[start / width][start % width].element == 'M' {
+        let free = *order
+            .iter()
+            .find(|index| board[*index / width][*index % width].element != 'M')
+            .expect("fewer mines than cells");
+        board[start / width][start % width].element = '0';
+        board[free / width][free % width].element = 'M';
+    }
+}
+
+/// Works out the number on every safe cell from the mines around it.
+pub(crate) fn place_numbers(board: &mut Board) {
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if board[y][x].element == 'M' {
+                continue;
+            }
+            let mines = board
+                .neighbours(y, x)
+                .filter(|(around_y, around_x)| board[*around_y][*around_x].element == 'M')
+                .count();
+            board[y][x].element = char::from_digit(mines as u32, 10).expect("at most 8 mines");
+        }
+    }
+}
+
+/// Counts the safe cells already uncovered and the safe cells in total.
+fn count_safe_cells(board: &Board) -> (usize, usize) {
+    board
+        .cells()
+        .filter(|cell| cell.element != 'M')
+        .fold((0, 0), |(revealed, safe), cell| {
+            (revealed + usize::from(!cell.hidden), safe + 1)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dump_board, parse_board};
+
+    fn pos(x: i32, y: i32) -> CellPos {
+        CellPos { x, y }
+    }
+
+    fn game(text: &str) -> Game {
+        let board = parse_board(text).unwrap();
+        let mines = board.cells().filter(|cell| cell.element == 'M').count() as i32;
+        let settings = Settings::new(board.width() as i32, board.height() as i32, mines);
+        Game::from_board(board, settings)
+    }
+
+    #[test]
+    fn first_reveal_is_safe_and_follows_the_seed() {
+        let mut settings = Settings::new(9, 9, 70);
+        for seed in 0..50 {
+            settings.set_seed(Some(seed));
+            let mut game = Game::new(settings);
+            game.reveal(pos(4, 4));
+            assert_eq!(game.status(), GameStatus::Playing);
+            let mines = game.board().cells().filter(|cell| cell.element == 'M');
+            assert_eq!(mines.count(), 70);
+            let mut again = Game::new(settings);
+            again.reveal(pos(4, 4));
+            assert_eq!(dump_board(game.board()), dump_board(again.board()));
+        }
+    }
+
+    #[test]
+    fn first_reveal_opens_when_there_is_room() {
+        let mut settings = Settings::new(9, 9, 10);
+        settings.set_seed(Some(7));
+        let mut game = Game::new(settings);
+        game.reveal(pos(0, 0));
+        assert_eq!(game.board()[0][0].element(), '0');
+    }
+
+    #[test]
+    fn flags_only_go_on_hidden_cells() {
+        let mut game = game("1*.\n");
+        assert!(game.toggle_flag(pos(0, 0)).is_empty());
+        assert_eq!(game.board()[0][0].mark(), Mark::None);
+        assert!(game.moves().is_empty());
+        assert!(!game.can_undo());
+        assert_eq!(game.toggle_flag(pos(1, 0)), vec![pos(1, 0)]);
+        assert!(game.board()[0][1].is_flagged());
+    }
+
+    #[test]
+    fn loaded_boards_are_timed_from_the_first_move() {
+        let mut game = game("1*.\n...\n");
+        game.record_cursor(pos(2, 0));
+        assert!(!game.is_timing());
+        game.reveal(pos(2, 0));
+        assert!(game.is_timing());
+    }
+
+    #[test]
+    fn chord_needs_matching_flags() {
+        let mut game = game("1*.\n...\n");
+        game.reveal(pos(2, 0));
+        assert!(game.chord(pos(2, 0)).is_empty());
+        assert_eq!(game.moves().len(), 1);
+        game.toggle_flag(pos(1, 0));
+        let mut changed = game.chord(pos(2, 0));
+        changed.sort_by_key(|pos| (pos.y, pos.x));
+        assert_eq!(changed, vec![pos(1, 1), pos(2, 1)]);
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_moves() {
+        let mut game = game("..*\n...\n");
+        assert!(game.undo().is_none());
+        game.reveal(pos(0, 0));
+        let opened = dump_board(game.board());
+        game.toggle_flag(pos(2, 0));
+        assert!(game.undo().is_some());
+        assert_eq!(dump_board(game.board()), opened);
+        assert!(game.undo().is_some());
+        assert_eq!(dump_board(game.board()), "..*\n...\n");
+        assert!(game.is_practice());
+        assert!(game.redo().is_some());
+        assert_eq!(dump_board(game.board()), opened);
+        assert!(game.redo().is_some());
+        assert!(game.board()[0][2].is_flagged());
+        assert!(game.redo().is_none());
+    }
+
+    #[test]
+    fn undo_takes_back_a_loss() {
+        let mut game = game(".*\n..\n");
+        game.reveal(pos(1, 0));
+        assert_eq!(game.status(), GameStatus::Lost);
+        game.undo();
+        assert_eq!(game.status(), GameStatus::Playing);
+        assert!(game.board()[0][1].hidden);
+        game.redo();
+        assert_eq!(game.status(), GameStatus::Lost);
+    }
+
+    #[test]
+    fn restart_goes_back_to_the_start() {
+        let mut game = game("1*.\nf..\n");
+        game.reveal(pos(2, 1));
+        game.toggle_flag(pos(1, 0));
+        let restarted = game.restart();
+        assert_eq!(dump_board(restarted.board()), "1*.\nf..\n");
+        assert!(restarted.moves().is_empty());
+        assert_eq!(restarted.start(), game.start());
+    }
+}
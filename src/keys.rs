@@ -0,0 +1,360 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Most keys that can be bound at once, so [`KeyBindings`] can stay `Copy`.
+const MAX_BINDINGS: usize = 32;
+
+/// Something the player can do from the keyboard during a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum KeyAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Reveal,
+    Chord,
+    Flag,
+    Hint,
+    Probabilities,
+    Undo,
+    Redo,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    Quit,
+}
+impl KeyAction {
+    pub const ALL: [KeyAction; 16] = [
+        KeyAction::MoveUp,
+        KeyAction::MoveDown,
+        KeyAction::MoveLeft,
+        KeyAction::MoveRight,
+        KeyAction::Reveal,
+        KeyAction::Chord,
+        KeyAction::Flag,
+        KeyAction::Hint,
+        KeyAction::Probabilities,
+        KeyAction::Undo,
+        KeyAction::Redo,
+        KeyAction::PanUp,
+        KeyAction::PanDown,
+        KeyAction::PanLeft,
+        KeyAction::PanRight,
+        KeyAction::Quit,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyAction::MoveUp => "Move up",
+            KeyAction::MoveDown => "Move down",
+            KeyAction::MoveLeft => "Move left",
+            KeyAction::MoveRight => "Move right",
+            KeyAction::Reveal => "Reveal",
+            KeyAction::Chord => "Chord",
+            KeyAction::Flag => "Flag",
+            KeyAction::Hint => "Hint",
+            KeyAction::Probabilities => "Mine odds",
+            KeyAction::Undo => "Undo",
+            KeyAction::Redo => "Redo",
+            KeyAction::PanUp => "Move board up",
+            KeyAction::PanDown => "Move board down",
+            KeyAction::PanLeft => "Move board left",
+            KeyAction::PanRight => "Move board right",
+            KeyAction::Quit => "Exit to main menu",
+        }
+    }
+    /// Whether a game can't be played or left without a key for this action.
+    pub fn is_required(&self) -> bool {
+        matches!(
+            self,
+            KeyAction::MoveUp
+                | KeyAction::MoveDown
+                | KeyAction::MoveLeft
+                | KeyAction::MoveRight
+                | KeyAction::Reveal
+                | KeyAction::Quit
+        )
+    }
+}
+
+/// A key as it is stored in the bindings. Letters are kept in lowercase so that they
+/// work with Shift or Caps Lock too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Esc,
+    Tab,
+    Backspace,
+}
+impl Key {
+    pub fn from_code(code: KeyCode) -> Option<Key> {
+        Some(match code {
+            KeyCode::Char(character) => Key::Char(character.to_ascii_lowercase()),
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Esc => Key::Esc,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::Backspace => Key::Backspace,
+            _ => return None,
+        })
+    }
+    pub fn name(&self) -> String {
+        match self {
+            Key::Char(' ') => "Space".to_owned(),
+            Key::Char(character) => character.to_ascii_uppercase().to_string(),
+            Key::Up => "Up".to_owned(),
+            Key::Down => "Down".to_owned(),
+            Key::Left => "Left".to_owned(),
+            Key::Right => "Right".to_owned(),
+            Key::Enter => "Enter".to_owned(),
+            Key::Esc => "Esc".to_owned(),
+            Key::Tab => "Tab".to_owned(),
+            Key::Backspace => "Backspace".to_owned(),
+        }
+    }
+    /// Reads a key back from its [`name`](Key::name), ignoring case.
+    pub fn parse(name: &str) -> Option<Key> {
+        let mut characters = name.chars();
+        if let (Some(character), None) = (characters.next(), characters.next()) {
+            return Some(Key::Char(character.to_ascii_lowercase()));
+        }
+        Some(match name.to_ascii_lowercase().as_str() {
+            "space" => Key::Char(' '),
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "enter" => Key::Enter,
+            "esc" => Key::Esc,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            _ => return None,
+        })
+    }
+}
+
+/// Which key does what. Each key has one action; an action can have several keys.
+///
+/// Stored in the config as a table from key names to actions.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(
+    into = "BTreeMap<String, KeyAction>",
+    try_from = "BTreeMap<String, KeyAction>"
+)]
+pub struct KeyBindings {
+    entries: [Option<(Key, KeyAction)>; MAX_BINDINGS],
+}
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::wasd()
+    }
+}
+impl KeyBindings {
+    /// The presets offered in the "Controls" menu.
+    pub fn presets() -> [(&'static str, KeyBindings); 3] {
+        [
+            ("WASD", KeyBindings::wasd()),
+            ("Vim (HJKL)", KeyBindings::vim()),
+            ("Numpad", KeyBindings::numpad()),
+        ]
+    }
+    pub fn wasd() -> Self {
+        KeyBindings::from_pairs(&[
+            ('w', KeyAction::MoveUp),
+            ('s', KeyAction::MoveDown),
+            ('a', KeyAction::MoveLeft),
+            ('d', KeyAction::MoveRight),
+            ('c', KeyAction::Reveal),
+            (' ', KeyAction::Chord),
+            ('f', KeyAction::Flag),
+            ('h', KeyAction::Hint),
+            ('p', KeyAction::Probabilities),
+            ('u', KeyAction::Undo),
+            ('r', KeyAction::Redo),
+        ])
+    }
+    pub fn vim() -> Self {
+        KeyBindings::from_pairs(&[
+            ('k', KeyAction::MoveUp),
+            ('j', KeyAction::MoveDown),
+            ('h', KeyAction::MoveLeft),
+            ('l', KeyAction::MoveRight),
+            (' ', KeyAction::Reveal),
+            ('d', KeyAction::Chord),
+            ('f', KeyAction::Flag),
+            ('n', KeyAction::Hint),
+            ('p', KeyAction::Probabilities),
+            ('u', KeyAction::Undo),
+            ('r', KeyAction::Redo),
+        ])
+    }
+    /// For a keypad with Num Lock on, which sends digits.
+    pub fn numpad() -> Self {
+        let mut bindings = KeyBindings::from_pairs(&[
+            ('8', KeyAction::MoveUp),
+            ('2', KeyAction::MoveDown),
+            ('4', KeyAction::MoveLeft),
+            ('6', KeyAction::MoveRight),
+            ('5', KeyAction::Reveal),
+            ('0', KeyAction::Flag),
+            ('.', KeyAction::Hint),
+            ('*', KeyAction::Probabilities),
+            ('/', KeyAction::Undo),
+            ('-', KeyAction::Redo),
+        ]);
+        bindings.bind(Key::Enter, KeyAction::Chord);
+        bindings
+    }
+    /// Every preset pans with the arrow keys and leaves with Esc.
+    fn from_pairs(pairs: &[(char, KeyAction)]) -> Self {
+        let mut bindings = KeyBindings {
+            entries: [None; MAX_BINDINGS],
+        };
+        for (character, action) in pairs {
+            bindings.bind(Key::Char(*character), *action);
+        }
+        bindings.bind(Key::Up, KeyAction::PanUp);
+        bindings.bind(Key::Down, KeyAction::PanDown);
+        bindings.bind(Key::Left, KeyAction::PanLeft);
+        bindings.bind(Key::Right, KeyAction::PanRight);
+        bindings.bind(Key::Esc, KeyAction::Quit);
+        bindings
+    }
+    pub fn action(&self, code: KeyCode) -> Option<KeyAction> {
+        let key = Key::from_code(code)?;
+        self.entries
+            .iter()
+            .flatten()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+    pub fn keys(&self, action: KeyAction) -> Vec<Key> {
+        self.entries
+            .iter()
+            .flatten()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+    /// The keys for `action` joined for instructions, like "C" or "Up/K".
+    pub fn describe(&self, action: KeyAction) -> String {
+        let keys: Vec<String> = self.keys(action).iter().map(Key::name).collect();
+        if keys.is_empty() {
+            "(unbound)".to_owned()
+        } else {
+            keys.join("/")
+        }
+    }
+    /// The first [required](KeyAction::is_required) action without a key, if any.
+    pub fn first_unbound(&self) -> Option<KeyAction> {
+        KeyAction::ALL
+            .into_iter()
+            .find(|action| action.is_required() && self.keys(*action).is_empty())
+    }
+    /// The required action that binding `key` to `action` would leave without a key.
+    pub fn would_unbind(&self, key: Key, action: KeyAction) -> Option<KeyAction> {
+        let (_, owner) = self
+            .entries
+            .iter()
+            .flatten()
+            .find(|(bound, _)| *bound == key)?;
+        (*owner != action && owner.is_required() && self.keys(*owner).len() == 1).then_some(*owner)
+    }
+    /// Makes `key` do `action`, replacing whatever it did before. Returns false when the
+    /// table is full.
+    pub fn bind(&mut self, key: Key, action: KeyAction) -> bool {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|(bound, _)| *bound == key)
+        {
+            entry.1 = action;
+            return true;
+        }
+        match self.entries.iter_mut().find(|entry| entry.is_none()) {
+            Some(entry) => {
+                *entry = Some((key, action));
+                true
+            }
+            None => false,
+        }
+    }
+    /// Removes every key bound to `action`.
+    pub fn unbind(&mut self, action: KeyAction) {
+        for entry in self.entries.iter_mut() {
+            if entry.is_some_and(|(_, bound)| bound == action) {
+                *entry = None;
+            }
+        }
+    }
+}
+impl From<KeyBindings> for BTreeMap<String, KeyAction> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings
+            .entries
+            .iter()
+            .flatten()
+            .map(|(key, action)| (key.name(), *action))
+            .collect()
+    }
+}
+impl TryFrom<BTreeMap<String, KeyAction>> for KeyBindings {
+    type Error = String;
+    fn try_from(table: BTreeMap<String, KeyAction>) -> Result<Self, Self::Error> {
+        let mut bindings = KeyBindings {
+            entries: [None; MAX_BINDINGS],
+        };
+        for (name, action) in table {
+            let key = Key::parse(&name).ok_or_else(|| format!("unknown key {name:?}"))?;
+            if !bindings.bind(key, action) {
+                return Err(format!("more than {MAX_BINDINGS} keys are bound"));
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_read_back() {
+        for key in [Key::Char(' '), Key::Char('k'), Key::Esc, Key::Backspace] {
+            assert_eq!(Key::parse(&key.name()), Some(key));
+        }
+        assert_eq!(Key::parse("ENTER"), Some(Key::Enter));
+        assert_eq!(Key::from_code(KeyCode::Char('K')), Some(Key::Char('k')));
+    }
+
+    #[test]
+    fn bindings_survive_the_config_table() {
+        let bindings = KeyBindings::default();
+        let table = BTreeMap::from(bindings);
+        let read_back = KeyBindings::try_from(table).unwrap();
+        for action in KeyAction::ALL {
+            assert_eq!(read_back.describe(action), bindings.describe(action));
+        }
+    }
+
+    #[test]
+    fn the_last_key_of_a_required_action_is_kept() {
+        let bindings = KeyBindings::default();
+        assert_eq!(
+            bindings.would_unbind(Key::Esc, KeyAction::Flag),
+            Some(KeyAction::Quit)
+        );
+        assert_eq!(bindings.would_unbind(Key::Esc, KeyAction::Quit), None);
+        assert_eq!(bindings.would_unbind(Key::Up, KeyAction::Flag), None);
+    }
+}
@@ -1,12 +1,13 @@
 use ansi_term::{
     ANSIGenericString,
-    Color::{Black, White, RGB},
+    Color::{self, Black, White, RGB},
 };
+use chrono::NaiveDate;
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
     event::{
-        read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
-        MouseButton, MouseEvent, MouseEventKind,
+        poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent,
+        KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
     },
     execute,
     style::ResetColor,
@@ -14,27 +15,52 @@ use crossterm::{
     ExecutableCommand,
 };
 use dialoguer::{theme::ColorfulTheme, Input, MultiSelect, Select};
-use rand::prelude::SliceRandom;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     cmp::{max, min},
-    collections::HashMap,
-    io::stdout,
+    fs,
+    io::{self, stdout, ErrorKind, Write},
     ops::ControlFlow,
+    path::{Path, PathBuf},
     process,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
 
-#[derive(Debug, Copy, Clone)]
-pub struct Cell {
-    hidden: bool,
-    element: char,
-    flagged: bool,
-    selected: bool,
-}
+mod board;
+mod board_file;
+mod config;
+mod daily;
+mod game;
+mod keys;
+mod metrics;
+mod puzzle;
+mod replay;
+mod save;
+mod solver;
+mod stats;
+mod tutorial;
+
+pub use board::Board;
+pub use board_file::{
+    dump_board, load_board_file, parse_board, parse_mbf, parse_rawvf, save_board_file,
+};
+pub use daily::{Daily, DailyHistory, DailyResult};
+pub use game::{
+    Cell, CellPos, Game, GameStatus, Mark, Move, MoveKind, StartState, NO_GUESS_TIME_LIMIT,
+};
+pub use keys::{Key, KeyAction, KeyBindings};
+pub use metrics::{three_bv, Metrics};
+pub use puzzle::{Puzzle, PuzzlePack, PuzzleProgress};
+pub use replay::{list_replays, save_replay, Replay};
+pub use save::{delete_saved_game, has_saved_game, load_game, save_game};
+pub use solver::{deduce, hint, mine_probabilities, Deductions, Hint, PROBABILITY_TIME_LIMIT};
+pub use stats::{board_name, BoardStats, Stats};
+
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct MousePos {
     x: i32,
@@ -42,33 +68,26 @@ pub struct MousePos {
 }
 impl MousePos {
     fn convert(&self, settings: &Settings) -> CellPos {
-        let cell_pos = CellPos {
-            x: ((self.x - settings.board_x_pos as i32) / 3)
-                .max(0)
-                .min(settings.width - 1),
-            y: (self.y - settings.board_y_pos as i32)
-                .max(0)
-                .min(settings.height - 1),
-        };
-        cell_pos
+        let (columns, rows) = view_size(settings);
+        CellPos {
+            x: settings.camera_x
+                + ((self.x - settings.board_x_pos as i32) / 3).clamp(0, columns - 1),
+            y: settings.camera_y + (self.y - settings.board_y_pos as i32).clamp(0, rows - 1),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-struct CellPos {
-    x: i32,
-    y: i32,
-}
 impl CellPos {
     pub fn convert(&self, settings: &Settings) -> MousePos {
         MousePos {
-            x: self.x + settings.board_x_pos as i32,
-            y: self.y + settings.board_y_pos as i32,
+            x: (self.x - settings.camera_x) * 3 + settings.board_x_pos as i32,
+            y: self.y - settings.camera_y + settings.board_y_pos as i32,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Settings {
     mines: i32,
     width: i32,
@@ -76,10 +95,42 @@ pub struct Settings {
     input_type: InputType,
     bordered: bool,
     centered: bool,
+    #[serde(skip)]
     board_x_pos: u32,
+    #[serde(skip)]
     board_y_pos: u32,
+    /// The top left cell in view, for boards larger than the terminal.
+    #[serde(skip)]
+    camera_x: i32,
+    #[serde(skip)]
+    camera_y: i32,
+    #[serde(skip)]
     str_y_pos: u32,
+    #[serde(skip)]
     showing_board: bool,
+    #[serde(skip)]
+    replaying: bool,
+    #[serde(skip)]
+    seed: Option<u64>,
+    /// The day whose board is being played, in a daily challenge.
+    #[serde(skip)]
+    daily: Option<NaiveDate>,
+    /// The [`Puzzle::id`] of the puzzle being played.
+    #[serde(skip)]
+    puzzle: Option<u64>,
+    /// Flags end the input wait like clicks do, so each one can be checked.
+    #[serde(skip)]
+    tutorial: bool,
+    /// The board was loaded from a file, so it is kept out of the statistics.
+    #[serde(skip)]
+    board_file: bool,
+    no_guess: bool,
+    #[serde(skip)]
+    show_probabilities: bool,
+    mouse_bindings: MouseBindings,
+    question_marks: bool,
+    theme: Theme,
+    key_bindings: KeyBindings,
 }
 impl Default for Settings {
     fn default() -> Self {
@@ -92,14 +143,101 @@ impl Default for Settings {
             centered: true,
             board_x_pos: 0,
             board_y_pos: 0,
+            camera_x: 0,
+            camera_y: 0,
             str_y_pos: 0,
             showing_board: false,
+            replaying: false,
+            seed: None,
+            daily: None,
+            puzzle: None,
+            tutorial: false,
+            board_file: false,
+            no_guess: false,
+            show_probabilities: false,
+            mouse_bindings: MouseBindings::default(),
+            question_marks: true,
+            theme: Theme::Classic,
+            key_bindings: KeyBindings::default(),
         }
     }
 }
+impl Settings {
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+    pub fn mines(&self) -> i32 {
+        self.mines
+    }
+    pub fn new(width: i32, height: i32, mines: i32) -> Self {
+        Settings {
+            mines,
+            width,
+            height,
+            ..Settings::default()
+        }
+    }
+    /// Fixes the seed used to place mines. `None` picks a new random seed every game.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+    /// Only generate boards that can be cleared by logic alone from the first click.
+    pub fn set_no_guess(&mut self, no_guess: bool) {
+        self.no_guess = no_guess;
+    }
+    /// Picks one of the preset boards by name: easy, normal or hard.
+    pub fn set_difficulty(&mut self, name: &str) -> Result<(), String> {
+        let difficulty = match name.to_ascii_lowercase().as_str() {
+            "easy" => Difficulty::Easy,
+            "normal" => Difficulty::Normal,
+            "hard" => Difficulty::Hard,
+            _ => {
+                return Err(format!(
+                    "Unknown difficulty {name:?}, expected easy, normal or hard"
+                ))
+            }
+        };
+        difficulty.apply(self);
+        Ok(())
+    }
+    /// Sets a custom board, checked the same way as the "Custom" difficulty menu.
+    pub fn set_size(&mut self, width: u32, height: u32, mines: u32) -> Result<(), String> {
+        validate_width(width)?;
+        validate_height(height)?;
+        validate_mines(mines, width, height)?;
+        self.width = width as i32;
+        self.height = height as i32;
+        self.mines = mines as i32;
+        Ok(())
+    }
+    pub fn set_keyboard(&mut self, keyboard: bool) {
+        self.input_type = if keyboard {
+            InputType::Keyboard
+        } else {
+            InputType::Mouse
+        };
+    }
+    /// Take mouse and keyboard input together.
+    pub fn set_hybrid(&mut self) {
+        self.input_type = InputType::Hybrid;
+    }
+    pub fn set_centered(&mut self, centered: bool) {
+        self.centered = centered;
+    }
+    pub fn set_bordered(&mut self, bordered: bool) {
+        self.bordered = bordered;
+    }
+}
 
 enum Choice {
     Click,
+    Chord,
+    /// A cell was flagged, already done by the time this is returned. Only used in the
+    /// tutorial.
+    Flag,
     Exit,
 }
 enum Difficulty {
@@ -108,15 +246,141 @@ enum Difficulty {
     Hard,
     Custom,
 }
-#[derive(Debug, Clone, Copy)]
+impl Difficulty {
+    /// Sets the board of a preset. `Custom` leaves the board as it is.
+    fn apply(&self, settings: &mut Settings) {
+        let (width, height, mines) = match self {
+            Difficulty::Easy => (8, 8, 10),
+            Difficulty::Normal => (16, 16, 40),
+            Difficulty::Hard => (30, 16, 99),
+            Difficulty::Custom => return,
+        };
+        settings.width = width;
+        settings.height = height;
+        settings.mines = mines;
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MouseAction {
+    Reveal,
+    Flag,
+    Chord,
+    Nothing,
+}
+impl MouseAction {
+    const ALL: [MouseAction; 4] = [
+        MouseAction::Reveal,
+        MouseAction::Flag,
+        MouseAction::Chord,
+        MouseAction::Nothing,
+    ];
+    fn name(&self) -> &'static str {
+        match self {
+            MouseAction::Reveal => "Reveal",
+            MouseAction::Flag => "Flag",
+            MouseAction::Chord => "Chord",
+            MouseAction::Nothing => "Nothing",
+        }
+    }
+}
+/// What each mouse button does in mouse mode. Pressing a second button while one is
+/// already held always chords.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MouseBindings {
+    pub left: MouseAction,
+    pub middle: MouseAction,
+    pub right: MouseAction,
+}
+impl Default for MouseBindings {
+    fn default() -> Self {
+        MouseBindings {
+            left: MouseAction::Reveal,
+            middle: MouseAction::Chord,
+            right: MouseAction::Flag,
+        }
+    }
+}
+impl MouseBindings {
+    fn action(&self, button: MouseButton) -> MouseAction {
+        match button {
+            MouseButton::Left => self.left,
+            MouseButton::Middle => self.middle,
+            MouseButton::Right => self.right,
+        }
+    }
+}
+/// Colours used to draw the board.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    Classic,
+    Dark,
+}
+impl Theme {
+    const ALL: [Theme; 2] = [Theme::Classic, Theme::Dark];
+    fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "Classic",
+            Theme::Dark => "Dark",
+        }
+    }
+    fn palette(&self) -> Palette {
+        match self {
+            Theme::Classic => Palette {
+                hidden: Black,
+                revealed: White,
+                mine: RGB(0, 0, 0),
+                numbers: [
+                    RGB(6, 3, 255),
+                    RGB(3, 122, 6),
+                    RGB(254, 0, 0),
+                    RGB(0, 0, 132),
+                    RGB(130, 1, 2),
+                    RGB(2, 127, 130),
+                    RGB(0, 0, 0),
+                    RGB(125, 125, 125),
+                ],
+            },
+            Theme::Dark => Palette {
+                hidden: RGB(90, 90, 90),
+                revealed: RGB(30, 30, 30),
+                mine: White,
+                numbers: [
+                    RGB(100, 149, 237),
+                    RGB(50, 205, 50),
+                    RGB(255, 99, 71),
+                    RGB(186, 85, 211),
+                    RGB(255, 165, 0),
+                    RGB(64, 224, 208),
+                    RGB(220, 220, 220),
+                    RGB(160, 160, 160),
+                ],
+            },
+        }
+    }
+}
+struct Palette {
+    hidden: Color,
+    revealed: Color,
+    mine: Color,
+    /// Colours of the numbers 1 to 8.
+    numbers: [Color; 8],
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 enum InputType {
     Mouse,
     Keyboard,
+    /// Mouse and keyboard at once, sharing one cursor.
+    Hybrid,
 }
-#[derive(PartialEq)]
-enum Click {
-    Dead,
-    Fine,
+impl InputType {
+    fn uses_mouse(&self) -> bool {
+        matches!(self, InputType::Mouse | InputType::Hybrid)
+    }
+    fn uses_keyboard(&self) -> bool {
+        matches!(self, InputType::Keyboard | InputType::Hybrid)
+    }
 }
 
 fn clear(settings: &mut Settings) {
@@ -130,52 +394,355 @@ fn clear(settings: &mut Settings) {
 
 fn center_board(settings: &mut Settings) {
     let terminal_size = get_terminal_size();
+    let border = settings.bordered as i32;
+    // The top row is kept free for the status line, and the row under the board for messages.
+    let top = 1 + border;
     if settings.centered {
-        settings.board_x_pos = ((terminal_size.0 / 2 - (settings.width * 3) / 2).max(0)) as u32;
-        settings.board_y_pos = ((terminal_size.1 / 2 - settings.height / 2).max(0)) as u32;
-        if settings.bordered {
-            settings.board_x_pos = (settings.board_x_pos as i32 - 1).max(1) as u32;
-            settings.board_y_pos = (settings.board_y_pos as i32 - 1).max(1) as u32;
-        }
-    } else if settings.bordered {
-        settings.board_x_pos = 1;
-        settings.board_y_pos = 1;
+        let width = (settings.width * 3).min(terminal_size.0 - 2 * border);
+        let height = settings.height.min(terminal_size.1 - top - border - 1);
+        settings.board_x_pos = ((terminal_size.0 - width) / 2).max(border) as u32;
+        settings.board_y_pos = ((terminal_size.1 - height) / 2).max(top) as u32;
     } else {
-        settings.board_x_pos = 0;
-        settings.board_y_pos = 0;
+        settings.board_x_pos = border as u32;
+        settings.board_y_pos = top as u32;
     }
+    clamp_camera(settings);
 }
 
-fn get_settings(settings: &mut Settings) {
-    let settings_options = vec!["Play", "Difficulty", "Controls", "Appearance", "Exit"];
+/// How many columns and rows of cells fit on screen from where the board is drawn.
+fn view_size(settings: &Settings) -> (i32, i32) {
+    let terminal_size = get_terminal_size();
+    let border = settings.bordered as i32;
+    let columns = (terminal_size.0 - settings.board_x_pos as i32 - border) / 3;
+    let rows = terminal_size.1 - settings.board_y_pos as i32 - border - 1;
+    (
+        columns.clamp(1, settings.width),
+        rows.clamp(1, settings.height),
+    )
+}
+/// Keeps the view inside the board.
+fn clamp_camera(settings: &mut Settings) {
+    let (columns, rows) = view_size(settings);
+    settings.camera_x = settings.camera_x.clamp(0, settings.width - columns);
+    settings.camera_y = settings.camera_y.clamp(0, settings.height - rows);
+}
+/// Scrolls the view by whole cells. Returns true when it moved.
+fn pan(settings: &mut Settings, x: i32, y: i32) -> bool {
+    let camera = (settings.camera_x, settings.camera_y);
+    settings.camera_x += x;
+    settings.camera_y += y;
+    clamp_camera(settings);
+    camera != (settings.camera_x, settings.camera_y)
+}
+/// Scrolls just far enough to show `cell_pos`. Returns true when the view moved.
+fn follow_cursor(settings: &mut Settings, cell_pos: CellPos) -> bool {
+    let (columns, rows) = view_size(settings);
+    let camera = (settings.camera_x, settings.camera_y);
+    settings.camera_x = settings
+        .camera_x
+        .clamp(cell_pos.x - columns + 1, cell_pos.x);
+    settings.camera_y = settings.camera_y.clamp(cell_pos.y - rows + 1, cell_pos.y);
+    clamp_camera(settings);
+    camera != (settings.camera_x, settings.camera_y)
+}
+fn center_camera(settings: &mut Settings, cell_pos: CellPos) {
+    let (columns, rows) = view_size(settings);
+    settings.camera_x = cell_pos.x - columns / 2;
+    settings.camera_y = cell_pos.y - rows / 2;
+    clamp_camera(settings);
+}
+/// Scrolls towards the side of the view the mouse rests on, if any.
+fn edge_scroll(settings: &mut Settings, mouse_pos: MousePos) -> bool {
+    let (columns, rows) = view_size(settings);
+    let column = (mouse_pos.x - settings.board_x_pos as i32).div_euclid(3);
+    let row = mouse_pos.y - settings.board_y_pos as i32;
+    let direction = |position: i32, size: i32| {
+        if position <= 0 {
+            -1
+        } else if position >= size - 1 {
+            1
+        } else {
+            0
+        }
+    };
+    pan(settings, direction(column, columns), direction(row, rows))
+}
+
+/// Runs the main menu until the player starts a game. Returns the saved game when
+/// "Resume" was picked, or `None` for a new game.
+fn get_settings(settings: &mut Settings) -> Option<Game> {
     loop {
+        let mut settings_options = vec![
+            "Play",
+            "Daily",
+            "Puzzles",
+            "Tutorial",
+            "Load board",
+            "Difficulty",
+            "Controls",
+            "Appearance",
+            "Gameplay",
+            "Seed",
+            "Statistics",
+            "Replays",
+            "Exit",
+        ];
+        if has_saved_game() {
+            settings_options.insert(0, "Resume");
+        }
         let setting = Select::with_theme(&ColorfulTheme::default())
             .items(&settings_options)
             .interact()
             .unwrap();
-        match setting {
-            0 => break,
-            1 => select_difficulty(settings),
-            2 => select_input_type(settings),
-            3 => get_appearance_settings(settings),
-            4 => exit_gracefully(),
+        match settings_options[setting] {
+            "Resume" => match load_game() {
+                Ok(Some(game)) => {
+                    delete_saved_game().ok();
+                    return Some(game);
+                }
+                Ok(None) => {}
+                Err(error) => println!("Could not resume the saved game: {error}"),
+            },
+            "Play" => return None,
+            "Daily" => {
+                if let Some(game) = select_daily(settings) {
+                    return Some(game);
+                }
+            }
+            "Puzzles" => {
+                if let Some(game) = select_puzzle(settings) {
+                    return Some(game);
+                }
+            }
+            "Tutorial" => play_tutorial(settings),
+            "Load board" => {
+                if let Some(game) = select_board_file(settings) {
+                    return Some(game);
+                }
+            }
+            "Difficulty" => select_difficulty(settings),
+            "Controls" => select_input_type(settings),
+            "Appearance" => get_appearance_settings(settings),
+            "Gameplay" => get_gameplay_settings(settings),
+            "Seed" => select_seed(settings),
+            "Statistics" => show_statistics(),
+            "Replays" => select_replay(settings),
+            "Exit" => exit_gracefully(),
             _ => {}
         }
+        if matches!(
+            settings_options[setting],
+            "Difficulty" | "Controls" | "Appearance" | "Gameplay"
+        ) {
+            if let Err(error) = settings.save() {
+                println!("Could not save settings: {error}");
+            }
+        }
     }
 }
 
-fn display_board(board: &Vec<Vec<Cell>>, settings: &mut Settings) {
+/// The "Daily" menu. Returns today's game when the player starts it.
+fn select_daily(settings: &Settings) -> Option<Game> {
+    let daily = Daily::today();
+    let play = format!("Play the board of {}", daily.date());
+    let options = [play.as_str(), "History", "Back"];
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .items(&options)
+        .interact()
+        .unwrap();
+    match choice {
+        0 => Some(start_daily(daily, settings)),
+        1 => {
+            show_daily_history();
+            None
+        }
+        _ => None,
+    }
+}
+/// Starts the board of `daily`, noting the attempt in the daily history. Whether it
+/// counts is told at the end of the game.
+fn start_daily(daily: Daily, settings: &Settings) -> Game {
+    let started = DailyHistory::load().and_then(|mut history| {
+        history.start(daily.date());
+        history.save()
+    });
+    if let Err(error) = started {
+        println!("Could not update the daily history: {error}");
+    }
+    daily.game(*settings)
+}
+fn show_daily_history() {
+    match DailyHistory::load() {
+        Ok(history) if history.days.is_empty() => println!("No daily boards played yet."),
+        Ok(history) => {
+            for (date, day) in history.days.iter().rev() {
+                let result = if !day.finished {
+                    "not finished".to_owned()
+                } else if day.practice {
+                    "practice, undo or hints used".to_owned()
+                } else if day.won {
+                    format!(
+                        "won in {}, 3BV {}, {:.2} 3BV/s",
+                        stats::format_time(day.time_ms),
+                        day.three_bv,
+                        day.three_bv_per_second
+                    )
+                } else {
+                    "lost".to_owned()
+                };
+                println!("{date}: {result}");
+            }
+            let won = history.days.values().filter(|day| day.won).count();
+            println!("Won {won} of {} days.", history.days.len());
+        }
+        Err(error) => println!("Could not load the daily history: {error}"),
+    }
+    Select::with_theme(&ColorfulTheme::default())
+        .items(&["Back"])
+        .interact()
+        .unwrap();
+}
+fn select_board_file(settings: &Settings) -> Option<Game> {
+    let path: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Board file (leave empty to go back)")
+        .allow_empty(true)
+        .interact_text()
+        .unwrap();
+    if path.trim().is_empty() {
+        return None;
+    }
+    match load_board_file(Path::new(path.trim()), *settings) {
+        Ok(game) => Some(game),
+        Err(error) => {
+            println!("Could not load the board: {error}");
+            None
+        }
+    }
+}
+/// The "Puzzles" menu: asks for a pack folder and lists its puzzles. Returns the game of
+/// the puzzle picked.
+fn select_puzzle(settings: &Settings) -> Option<Game> {
+    let mut progress = PuzzleProgress::load().unwrap_or_else(|error| {
+        println!("Could not load puzzle progress: {error}");
+        PuzzleProgress::default()
+    });
+    let dir: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Puzzle folder (leave empty to go back)")
+        .with_initial_text(progress.last_pack.clone().unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()
+        .unwrap();
+    if dir.trim().is_empty() {
+        return None;
+    }
+    let pack = match PuzzlePack::load(Path::new(dir.trim())) {
+        Ok(pack) => pack,
+        Err(error) => {
+            println!("Could not load the puzzles: {error}");
+            return None;
+        }
+    };
+    for rejected in &pack.rejected {
+        println!("Skipped {rejected}");
+    }
+    if pack.puzzles.is_empty() {
+        println!("No puzzles found.");
+        return None;
+    }
+    progress.last_pack = Some(dir.trim().to_owned());
+    if let Err(error) = progress.save() {
+        println!("Could not save puzzle progress: {error}");
+    }
+    let solved = pack
+        .puzzles
+        .iter()
+        .filter(|puzzle| progress.is_solved(puzzle.id()))
+        .count();
+    println!("Solved {solved} of {}.", pack.puzzles.len());
+    let mut options: Vec<String> = pack
+        .puzzles
+        .iter()
+        .map(|puzzle| {
+            let mark = if progress.is_solved(puzzle.id()) {
+                "✓"
+            } else {
+                " "
+            };
+            format!("{mark} {}", puzzle.name())
+        })
+        .collect();
+    options.push("Back".to_owned());
+    // Start on the first puzzle not solved yet.
+    let first_unsolved = pack
+        .puzzles
+        .iter()
+        .position(|puzzle| !progress.is_solved(puzzle.id()))
+        .unwrap_or(0);
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .items(&options)
+        .default(first_unsolved)
+        .interact()
+        .unwrap();
+    pack.puzzles
+        .get(choice)
+        .map(|puzzle| puzzle.game(*settings))
+}
+
+/// The mine odds tinting hidden cells, by [`Board::index_of`], while the overlay is on.
+/// They belong to the drawing, not to the [`Game`], so the renderer keeps them.
+static MINE_ODDS: Mutex<Vec<Option<f64>>> = Mutex::new(Vec::new());
+
+/// Where a file of the given name lives in the user's data directory.
+pub(crate) fn data_path(file_name: &str) -> io::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join("rustsweeper").join(file_name))
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no data directory found"))
+}
+
+/// Reads a TOML file, or `None` when there is no such file. Errors name the file.
+pub(crate) fn load_toml<T: DeserializeOwned>(path: &Path) -> io::Result<Option<T>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(io::Error::new(
+                error.kind(),
+                format!("{}: {error}", path.display()),
+            ))
+        }
+    };
+    toml::from_str(&contents).map(Some).map_err(|error| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} is invalid: {}", path.display(), error.message()),
+        )
+    })
+}
+
+/// Writes `value` to a TOML file, creating its directory first. TOML integers stop at
+/// `i64::MAX`, so seeds and other `u64`s go in as text.
+pub(crate) fn save_toml<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = toml::to_string(value)
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error.to_string()))?;
+    fs::write(path, contents)
+}
+
+fn display_board(board: &Board, settings: &mut Settings) {
     disable_raw_mode().unwrap();
     clear(settings);
     let terminal_size = get_terminal_size();
-    for y in 0..settings.height {
-        for x in 0..settings.width {
-            update_cell(&board, CellPos { x, y }, &settings);
+    let (columns, rows) = view_size(settings);
+    for y in settings.camera_y..settings.camera_y + rows {
+        for x in settings.camera_x..settings.camera_x + columns {
+            update_cell(board, CellPos { x, y }, settings);
         }
     }
     let mut tip_pos = (
         settings.board_x_pos as i32,
-        settings.height + settings.board_y_pos as i32,
+        rows + settings.board_y_pos as i32,
     );
     let mut y_limit = terminal_size.1;
     if settings.bordered {
@@ -183,26 +750,59 @@ fn display_board(board: &Vec<Vec<Cell>>, settings: &mut Settings) {
         tip_pos.1 += 1;
         tip_pos.0 -= 1;
         for j in 0..2 {
-            for i in 0..settings.width {
+            for i in 0..columns {
                 draw_x(settings, i, j, terminal_size);
             }
         }
         for j in 0..2 {
-            for i in -1..settings.height + 1 {
+            for i in -1..rows + 1 {
                 draw_y(settings, i, j, terminal_size);
             }
         }
     }
     settings.showing_board = true;
     if tip_pos.1 < y_limit {
-        if let InputType::Keyboard = settings.input_type {
+        if settings.replaying {
             print_string(
-                "WASD to move around, C to Click, F to Flag and ESC to exit to main menu. Use arrow keys to move board",
+                "Space to pause, N for the next move while paused, 1-4 for 0.5x/1x/2x/4x speed and ESC to exit to main menu",
                 settings,
             );
         } else {
+            let keys = settings.key_bindings;
+            let mut controls = vec![];
+            if settings.input_type.uses_mouse() {
+                let buttons = settings.mouse_bindings;
+                controls.push(format!(
+                    "Left Mouse to {}, Middle Mouse to {}, Right Mouse to {}",
+                    buttons.left.name(),
+                    buttons.middle.name(),
+                    buttons.right.name(),
+                ));
+            }
+            if settings.input_type.uses_keyboard() {
+                controls.push(format!(
+                    "{}/{}/{}/{} to move around, {} to Click, {} to Chord",
+                    keys.describe(KeyAction::MoveUp),
+                    keys.describe(KeyAction::MoveLeft),
+                    keys.describe(KeyAction::MoveDown),
+                    keys.describe(KeyAction::MoveRight),
+                    keys.describe(KeyAction::Reveal),
+                    keys.describe(KeyAction::Chord),
+                ));
+            }
+            let controls = controls.join(", ");
             print_string(
-                "Left Mouse Button to Click, F to Flag and ESC to exit to main menu. Use arrow keys to move board",
+                &format!(
+                    "{controls}, {} to Flag, {} for a Hint, {} for mine odds and {} to exit to main menu. Use {}/{}/{}/{} to move board",
+                    keys.describe(KeyAction::Flag),
+                    keys.describe(KeyAction::Hint),
+                    keys.describe(KeyAction::Probabilities),
+                    keys.describe(KeyAction::Quit),
+                    keys.describe(KeyAction::PanUp),
+                    keys.describe(KeyAction::PanLeft),
+                    keys.describe(KeyAction::PanDown),
+                    keys.describe(KeyAction::PanRight),
+                ),
                 settings,
             );
         }
@@ -210,10 +810,11 @@ fn display_board(board: &Vec<Vec<Cell>>, settings: &mut Settings) {
 }
 
 fn draw_y(settings: &mut Settings, i: i32, j: i32, terminal_size: (i32, i32)) {
+    let (columns, rows) = view_size(settings);
     let mut move_to_x = settings.board_x_pos as i32 - 1;
     let move_to_y = settings.board_y_pos as i32 + i;
     if j == 1 {
-        move_to_x += settings.width * 3 + 1
+        move_to_x += columns * 3 + 1
     }
     if move_to_x >= 0
         && move_to_x < terminal_size.0
@@ -230,7 +831,7 @@ fn draw_y(settings: &mut Settings, i: i32, j: i32, terminal_size: (i32, i32)) {
                 1 => "┓",
                 _ => "",
             };
-        } else if i == settings.height {
+        } else if i == rows {
             char = match j {
                 0 => "┗",
                 1 => "┛",
@@ -247,7 +848,7 @@ fn draw_x(settings: &mut Settings, i: i32, j: i32, terminal_size: (i32, i32)) {
     let move_to_x = settings.board_x_pos as i32 + i * 3;
     let mut move_to_y = settings.board_y_pos as i32 - 1;
     if j == 1 {
-        move_to_y += settings.height + 1
+        move_to_y += view_size(settings).1 + 1
     }
     if move_to_x >= 0
         && move_to_x < terminal_size.0
@@ -261,188 +862,267 @@ fn draw_x(settings: &mut Settings, i: i32, j: i32, terminal_size: (i32, i32)) {
     }
 }
 
+/// The mouse buttons held down. It outlives each call of [`get_choice_from_user`], which
+/// returns as soon as a chord is made, so that letting go of the chord's buttons afterwards
+/// doesn't also do what each button does alone.
+#[derive(Default)]
+struct MouseGesture {
+    held: Vec<MouseButton>,
+    chorded: bool,
+}
+
 fn get_choice_from_user(
-    mut board: &mut Vec<Vec<Cell>>,
+    game: &mut Game,
     settings: Arc<Mutex<Settings>>,
     starting_pos: CellPos,
+    gesture: &mut MouseGesture,
 ) -> (Choice, CellPos) {
     let mut cell_pos = starting_pos;
     let mut previous_select_pos = cell_pos;
     let settings_mutex: Arc<Mutex<Settings>> = Arc::clone(&settings);
 
     let mut mouse_pos = cell_pos.convert(&settings_mutex.lock().unwrap());
+    // Edge scrolling only follows the mouse while it is the input being used.
+    let mut mouse_last = false;
     let choice: Choice;
     let thread_flag = Arc::new(AtomicBool::new(false));
     let flag_clone = thread_flag.clone();
-    let mut terminal_size = get_terminal_size();
-    let (tx, rx) = std::sync::mpsc::channel::<Vec<Vec<Cell>>>();
+    let (tx, rx) = std::sync::mpsc::channel::<HudState>();
 
+    // Keeps the timer on the status line ticking while waiting for input.
     let cloned_mutex = Arc::clone(&settings);
     let handle = thread::spawn(move || {
-        let mut board_copy: Option<Vec<Vec<Cell>>> = None;
+        let mut state: Option<HudState> = None;
+        let mut hud = String::new();
         while !flag_clone.load(Ordering::Relaxed) {
             if let Ok(received_data) = rx.try_recv() {
-                board_copy = Some(received_data)
+                state = Some(received_data)
             };
-            let new_terminal_size = get_terminal_size();
-            if terminal_size != new_terminal_size {
-                clear(&mut cloned_mutex.lock().unwrap());
-                terminal_size = new_terminal_size;
-                if let Some(ref board_copy) = board_copy {
-                    center_board(&mut cloned_mutex.lock().unwrap());
-                    display_board(board_copy, &mut cloned_mutex.lock().unwrap());
+            if let Some(ref state) = state {
+                let new_hud = get_hud_string(state, &cloned_mutex.lock().unwrap());
+                if new_hud != hud {
+                    draw_hud(&new_hud, &cloned_mutex.lock().unwrap());
+                    hud = new_hud;
                 }
             }
+            thread::sleep(Duration::from_millis(50));
         }
     });
+    tx.send(HudState::of(game)).unwrap();
     stdout().execute(EnableMouseCapture).unwrap();
     loop {
         enable_raw_mode().unwrap();
         stdout().execute(Hide).unwrap();
-        match read().unwrap() {
-            Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Down(MouseButton::Left),
-                ..
-            }) => {
-                let settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Mouse = settings_guard.input_type {
-                    choice = Choice::Click;
-                    break;
-                }
-                drop(settings_guard);
-            }
-            Event::Mouse(MouseEvent { row, column, .. }) => {
-                let mut settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Mouse = settings_guard.input_type {
-                    mouse_pos.x = column as i32;
-                    mouse_pos.y = row as i32;
-                    cell_pos = mouse_pos.convert(&mut settings_guard);
-                }
-                drop(settings_guard);
+        if !poll(EDGE_SCROLL_INTERVAL).unwrap() {
+            // Resting the mouse on the side of the view keeps scrolling that way.
+            let mut settings_guard = settings_mutex.lock().unwrap();
+            if mouse_last && edge_scroll(&mut settings_guard, mouse_pos) {
+                cell_pos = mouse_pos.convert(&settings_guard);
+                display_board(&game.board, &mut settings_guard);
+                tx.send(HudState::of(game)).unwrap();
             }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('a'),
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Keyboard = settings_guard.input_type {
-                    cell_pos.x = max(0, min(cell_pos.x - 1, settings_guard.width - 1));
+            drop(settings_guard);
+        } else {
+            match read().unwrap() {
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(button),
+                    row,
+                    column,
+                    ..
+                }) => {
+                    let settings_guard = settings_mutex.lock().unwrap();
+                    if settings_guard.input_type.uses_mouse() {
+                        // The keyboard may have moved the cursor since the mouse last did.
+                        mouse_pos.x = column as i32;
+                        mouse_pos.y = row as i32;
+                        cell_pos = mouse_pos.convert(&settings_guard);
+                        if !gesture.held.contains(&button) {
+                            gesture.held.push(button);
+                        }
+                        // A second button makes a chord. A single button only acts once it
+                        // is let go, as until then a second one may still follow.
+                        if gesture.held.len() > 1 && !gesture.chorded {
+                            gesture.chorded = true;
+                            choice = Choice::Chord;
+                            break;
+                        }
+                    }
+                    drop(settings_guard);
                 }
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('d'),
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Keyboard = settings_guard.input_type {
-                    cell_pos.x = max(0, min(cell_pos.x + 1, settings_guard.width - 1));
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Up(button),
+                    row,
+                    column,
+                    ..
+                }) => {
+                    let settings_guard = settings_mutex.lock().unwrap();
+                    let was_held = gesture.held.contains(&button);
+                    gesture.held.retain(|held| *held != button);
+                    if gesture.chorded {
+                        gesture.chorded = !gesture.held.is_empty();
+                    } else if was_held && settings_guard.input_type.uses_mouse() {
+                        mouse_pos.x = column as i32;
+                        mouse_pos.y = row as i32;
+                        cell_pos = mouse_pos.convert(&settings_guard);
+                        match settings_guard.mouse_bindings.action(button) {
+                            MouseAction::Reveal => {
+                                choice = Choice::Click;
+                                break;
+                            }
+                            MouseAction::Chord => {
+                                choice = Choice::Chord;
+                                break;
+                            }
+                            MouseAction::Flag => {
+                                flag_cell(game, cell_pos, &settings_guard);
+                                tx.send(HudState::of(game)).unwrap();
+                                if settings_guard.tutorial {
+                                    choice = Choice::Flag;
+                                    break;
+                                }
+                            }
+                            MouseAction::Nothing => {}
+                        }
+                    }
+                    drop(settings_guard);
                 }
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('w'),
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Keyboard = settings_guard.input_type {
-                    cell_pos.y = max(0, min(cell_pos.y - 1, settings_guard.height - 1));
+                Event::Mouse(MouseEvent { row, column, .. }) => {
+                    let settings_guard = settings_mutex.lock().unwrap();
+                    if settings_guard.input_type.uses_mouse() {
+                        mouse_pos.x = column as i32;
+                        mouse_pos.y = row as i32;
+                        cell_pos = mouse_pos.convert(&settings_guard);
+                        mouse_last = true;
+                    }
+                    drop(settings_guard);
                 }
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('s'),
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Keyboard = settings_guard.input_type {
-                    cell_pos.y = max(0, min(cell_pos.y + 1, settings_guard.height - 1));
+                Event::Resize(..) => {
+                    let mut settings_guard = settings_mutex.lock().unwrap();
+                    clear(&mut settings_guard);
+                    center_board(&mut settings_guard);
+                    display_board(&game.board, &mut settings_guard);
+                    draw_hud(
+                        &get_hud_string(&HudState::of(game), &settings_guard),
+                        &settings_guard,
+                    );
+                    drop(settings_guard);
                 }
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let settings_guard = settings_mutex.lock().unwrap();
-                if let InputType::Keyboard = settings_guard.input_type {
-                    choice = Choice::Click;
-                    break;
+                Event::Key(KeyEvent {
+                    code,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    mouse_last = false;
+                    let mut settings_guard = settings_mutex.lock().unwrap();
+                    // Moving the selection and clicking are mouse jobs in mouse mode.
+                    let keyboard = settings_guard.input_type.uses_keyboard();
+                    let (width, height) = (settings_guard.width, settings_guard.height);
+                    match settings_guard.key_bindings.action(code) {
+                        Some(KeyAction::MoveUp) if keyboard => {
+                            cell_pos.y = max(0, min(cell_pos.y - 1, height - 1));
+                        }
+                        Some(KeyAction::MoveDown) if keyboard => {
+                            cell_pos.y = max(0, min(cell_pos.y + 1, height - 1));
+                        }
+                        Some(KeyAction::MoveLeft) if keyboard => {
+                            cell_pos.x = max(0, min(cell_pos.x - 1, width - 1));
+                        }
+                        Some(KeyAction::MoveRight) if keyboard => {
+                            cell_pos.x = max(0, min(cell_pos.x + 1, width - 1));
+                        }
+                        Some(KeyAction::Reveal) if keyboard => {
+                            choice = Choice::Click;
+                            break;
+                        }
+                        Some(KeyAction::Chord) if keyboard => {
+                            choice = Choice::Chord;
+                            break;
+                        }
+                        Some(KeyAction::Flag) => {
+                            flag_cell(game, cell_pos, &settings_guard);
+                            tx.send(HudState::of(game)).unwrap();
+                            if settings_guard.tutorial {
+                                choice = Choice::Flag;
+                                break;
+                            }
+                        }
+                        Some(KeyAction::Probabilities) => {
+                            settings_guard.show_probabilities = !settings_guard.show_probabilities;
+                            update_probabilities(game, &settings_guard);
+                            if settings_guard.show_probabilities
+                                && game.status() == GameStatus::Playing
+                                && MINE_ODDS.lock().unwrap().is_empty()
+                            {
+                                print_string(
+                                    "The odds can't be worked out in time on this board.",
+                                    &mut settings_guard,
+                                );
+                            }
+                        }
+                        Some(KeyAction::Hint) => match game.hint() {
+                            Some(hint) => display_hint(hint, &settings_guard),
+                            None => print_string("No certain move found.", &mut settings_guard),
+                        },
+                        Some(action @ (KeyAction::Undo | KeyAction::Redo)) => {
+                            let changed = if action == KeyAction::Undo {
+                                game.undo()
+                            } else {
+                                game.redo()
+                            };
+                            match changed {
+                                Some(changed) => {
+                                    update_cells(&game.board, changed, &settings_guard);
+                                    update_probabilities(game, &settings_guard);
+                                    tx.send(HudState::of(game)).unwrap();
+                                }
+                                None if action == KeyAction::Undo => {
+                                    print_string("Nothing to undo.", &mut settings_guard)
+                                }
+                                None => print_string("Nothing to redo.", &mut settings_guard),
+                            }
+                        }
+                        Some(
+                            action @ (KeyAction::PanUp
+                            | KeyAction::PanDown
+                            | KeyAction::PanLeft
+                            | KeyAction::PanRight),
+                        ) => {
+                            let (x, y) = match action {
+                                KeyAction::PanUp => (0, -1),
+                                KeyAction::PanDown => (0, 1),
+                                KeyAction::PanLeft => (-1, 0),
+                                _ => (1, 0),
+                            };
+                            if pan(&mut settings_guard, x, y) {
+                                display_board(&game.board, &mut settings_guard);
+                                tx.send(HudState::of(game)).unwrap();
+                            }
+                        }
+                        Some(KeyAction::Quit) => {
+                            choice = Choice::Exit;
+                            break;
+                        }
+                        _ => {}
+                    }
+                    drop(settings_guard);
                 }
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('f'),
-                kind: KeyEventKind::Press,
-                ..
-            }) => flag(board, cell_pos, &settings_mutex.lock().unwrap()),
-            Event::Key(KeyEvent {
-                code: KeyCode::Up,
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let mut settings_guard = settings_mutex.lock().unwrap();
-                settings_guard.board_y_pos = (settings_guard.board_y_pos as i32 - 1).max(0) as u32;
-                display_board(board, &mut settings_guard);
-                tx.send(board.clone()).unwrap();
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Down,
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let mut settings_guard = settings_mutex.lock().unwrap();
-                settings_guard.board_y_pos += 1;
-                display_board(board, &mut settings_guard);
-                tx.send(board.clone()).unwrap();
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Right,
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let mut settings_guard = settings_mutex.lock().unwrap();
-                settings_guard.board_x_pos += 1;
-                display_board(board, &mut settings_guard);
-                tx.send(board.clone()).unwrap();
-                drop(settings_guard);
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Left,
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                let mut settings_guard = settings_mutex.lock().unwrap();
-                settings_guard.board_x_pos = (settings_guard.board_x_pos as i32 - 1).max(0) as u32;
-                display_board(board, &mut settings_guard);
-                tx.send(board.clone()).unwrap();
-                drop(settings_guard);
+                _ => {}
             }
-            Event::Key(KeyEvent {
-                code: KeyCode::Esc,
-                kind: KeyEventKind::Press,
-                ..
-            }) => {
-                choice = Choice::Exit;
-                break;
-            }
-            _ => {}
         }
         if cell_pos != previous_select_pos {
-            let settings_guard = settings_mutex.lock().unwrap();
-            board[previous_select_pos.y as usize][previous_select_pos.x as usize].selected = false;
-            update_cell(board, previous_select_pos, &settings_guard);
-            board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
-            update_cell(board, cell_pos, &settings_guard);
+            let mut settings_guard = settings_mutex.lock().unwrap();
+            game.record_cursor(cell_pos);
+            game.board[previous_select_pos.y as usize][previous_select_pos.x as usize].selected =
+                false;
+            game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
+            if follow_cursor(&mut settings_guard, cell_pos) {
+                display_board(&game.board, &mut settings_guard);
+            } else {
+                update_cell(&game.board, previous_select_pos, &settings_guard);
+                update_cell(&game.board, cell_pos, &settings_guard);
+            }
             previous_select_pos = cell_pos;
-            tx.send(board.clone()).unwrap();
+            tx.send(HudState::of(game)).unwrap();
             drop(settings_guard);
         }
     }
@@ -454,104 +1134,270 @@ fn get_choice_from_user(
     (choice, cell_pos)
 }
 
-fn place_mines(board: &mut Vec<Vec<Cell>>, settings: &Settings, starting_coords: CellPos) {
-    let cell_amount = settings.width * settings.height;
-    let mut indices: Vec<usize> = vec![];
-    for i in 0..cell_amount as usize {
-        let column_number = (i as i32) / settings.width;
-        let row_number = (i as i32) % settings.width;
-        if (starting_coords.y - column_number).abs() <= 1
-            && (starting_coords.x - row_number).abs() <= 1
-        {
-            continue;
-        }
-        indices.push(i);
-    }
-    let choices: Vec<&usize> = indices
-        .choose_multiple(&mut rand::thread_rng(), settings.mines as usize)
-        .collect();
-    for index in choices {
-        let row_index = index / settings.width as usize;
-        let column_index = index % settings.width as usize;
-        board[row_index][column_index].element = 'M';
-    }
-}
-
-fn place_numbers(board: &mut Vec<Vec<Cell>>, settings: &Settings) {
-    let mut board_copy = board.clone();
-    for (row_number, row) in board.iter().enumerate() {
-        for (column_number, cell) in row.iter().enumerate() {
-            let around = get_around_cell([row_number, column_number], board, settings);
-            let mut number = 0;
-            for i in around.iter() {
-                if i.0 == 'M' {
-                    number += 1;
-                }
-            }
-            if cell.element != 'M' {
-                board_copy[row_number][column_number].element =
-                    char::from_digit(number, 10).expect("Fuck");
-            }
-        }
-    }
-    board.clone_from(&board_copy);
-}
 fn get_terminal_size() -> (i32, i32) {
     let size = terminal_size::terminal_size().unwrap();
     (size.0 .0 as i32, size.1 .0 as i32)
 }
 fn game_play_loop_node(
-    board: &mut Vec<Vec<Cell>>,
+    game: &mut Game,
     settings: &mut Settings,
     choice: &Choice,
     cell_pos: CellPos,
-    hidden_cells: &mut Vec<(usize, usize)>,
+    menu_settings: &Settings,
 ) -> ControlFlow<()> {
     match choice {
         Choice::Exit => {
-            main_menu(*settings, false);
+            if let Err(error) = save_game(game) {
+                print_string(&format!("Could not save the game: {error}"), settings);
+            }
+            main_menu(*menu_settings, false);
         }
-        Choice::Click => {
+        Choice::Flag => {}
+        Choice::Click | Choice::Chord => {
             let terminal_size = get_terminal_size();
-            let event = event(cell_pos, board, settings, hidden_cells);
-            match event {
-                Click::Dead => {
-                    if terminal_size.1 > settings.height + 4 {
-                        reveal_board(board, settings);
-                    } else {
-                        clear(settings);
-                    }
-                    print_string("You died.", settings);
-                    return ControlFlow::Break(());
+            let first_click = game.status() == GameStatus::NotStarted;
+            let cell = game.board[cell_pos.y as usize][cell_pos.x as usize];
+            // Clicking a revealed number chords it, like the dedicated chord inputs.
+            let changed = if matches!(choice, Choice::Chord) || !cell.hidden {
+                game.chord(cell_pos)
+            } else {
+                game.reveal(cell_pos)
+            };
+            update_cells(&game.board, changed, settings);
+            update_probabilities(game, settings);
+            if first_click && game.no_guess_fallback() {
+                print_string(
+                    "No guess-free board found in time, this board may need guessing.",
+                    settings,
+                );
+            }
+            let rows = view_size(settings).1;
+            if game.status() == GameStatus::Lost {
+                if terminal_size.1 > rows + 4 {
+                    reveal_board(game, settings);
+                } else {
+                    clear(settings);
+                }
+                draw_hud(&get_hud_string(&HudState::of(game), settings), settings);
+                print_string("You died.", settings);
+                if offer_undo(game, settings) {
+                    game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
+                    clear(settings);
+                    display_board(&game.board, settings);
+                    return ControlFlow::Continue(());
                 }
-                Click::Fine => {}
+                print_result(game, settings);
+                return ControlFlow::Break(());
             }
-            if won(hidden_cells) {
-                if terminal_size.1 > settings.height + 4 {
-                    reveal_board(board, settings);
+            if game.status() == GameStatus::Won {
+                if terminal_size.1 > rows + 4 {
+                    reveal_board(game, settings);
                 } else {
                     clear(settings);
                 }
+                draw_hud(&get_hud_string(&HudState::of(game), settings), settings);
                 print_string("You win!", settings);
+                print_result(game, settings);
                 return ControlFlow::Break(());
             }
         }
     };
     ControlFlow::Continue(())
 }
+/// Asks whether to take back the move that lost the game. Returns true if it was undone.
+fn offer_undo(game: &mut Game, settings: &mut Settings) -> bool {
+    if !game.can_undo() || settings.key_bindings.keys(KeyAction::Undo).is_empty() {
+        return false;
+    }
+    print_string(
+        &format!(
+            "Press {} to take that move back, or any other key to continue.",
+            settings.key_bindings.describe(KeyAction::Undo)
+        ),
+        settings,
+    );
+    enable_raw_mode().unwrap();
+    let code = loop {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = read().unwrap()
+        {
+            break code;
+        }
+    };
+    disable_raw_mode().unwrap();
+    settings.key_bindings.action(code) == Some(KeyAction::Undo) && game.undo().is_some()
+}
+/// What the status line shows of a game. The status line thread gets a new one after
+/// every input, so it is kept small instead of being a copy of the game.
+#[derive(Debug, Clone, Copy)]
+struct HudState {
+    flags: usize,
+    mines: i32,
+    status: GameStatus,
+    practice: bool,
+    elapsed: Duration,
+    /// When `elapsed` was taken, if the clock was still running then.
+    taken_at: Option<Instant>,
+}
+impl HudState {
+    fn of(game: &Game) -> Self {
+        HudState {
+            flags: game.flags(),
+            mines: game.settings().mines,
+            status: game.status(),
+            practice: game.is_practice(),
+            elapsed: game.elapsed(),
+            taken_at: game.is_timing().then(Instant::now),
+        }
+    }
+    fn elapsed(&self) -> Duration {
+        self.elapsed
+            + self
+                .taken_at
+                .map_or(Duration::ZERO, |taken_at| taken_at.elapsed())
+    }
+}
+fn get_hud_string(state: &HudState, settings: &Settings) -> String {
+    let hud = format_hud(state, state.elapsed());
+    let (columns, rows) = view_size(settings);
+    if (columns, rows) == (settings.width, settings.height) {
+        return hud;
+    }
+    // Shows which part of a large board is on screen.
+    format!(
+        "{hud}  x {}-{}/{} y {}-{}/{}",
+        settings.camera_x + 1,
+        settings.camera_x + columns,
+        settings.width,
+        settings.camera_y + 1,
+        settings.camera_y + rows,
+        settings.height
+    )
+}
+fn format_hud(state: &HudState, elapsed: Duration) -> String {
+    let face = match state.status {
+        GameStatus::NotStarted | GameStatus::Playing => ":)",
+        GameStatus::Won => "B)",
+        GameStatus::Lost => "X(",
+    };
+    let practice = if state.practice { "  practice" } else { "" };
+    format!(
+        "⚑ {}/{}  {:03}s  {face}{practice}",
+        state.flags,
+        state.mines,
+        elapsed.as_secs()
+    )
+}
+/// Draws the status line on the row above the board.
+fn draw_hud(hud: &str, settings: &Settings) {
+    let y_pos = settings.board_y_pos as i32 - 1 - settings.bordered as i32;
+    let x_pos = (settings.board_x_pos as i32 - settings.bordered as i32).max(0);
+    if y_pos < 0 || y_pos >= get_terminal_size().1 {
+        return;
+    }
+    let width = (view_size(settings).0 * 3 + 2 * settings.bordered as i32) as usize;
+    stdout()
+        .execute(MoveTo(x_pos as u16, y_pos as u16))
+        .unwrap();
+    print!("{hud:<width$}");
+    stdout().flush().unwrap();
+}
+fn print_result(game: &Game, settings: &mut Settings) {
+    // Puzzles and loaded boards come with their mines, so they have no seed.
+    if game.settings().puzzle.is_none() && !game.settings().board_file {
+        print_string(&format!("Seed: {}", game.seed()), settings);
+    }
+    if game.hints_used() > 0 {
+        print_string(&format!("Hints used: {}", game.hints_used()), settings);
+    }
+    if game.is_practice() {
+        print_string("Practice game, not counted towards best times.", settings);
+    }
+    let metrics = game.metrics();
+    for line in metrics.describe() {
+        print_string(&line, settings);
+    }
+    if let Err(error) = save_replay(game) {
+        print_string(&format!("Could not save the replay: {error}"), settings);
+    }
+    if let Some(date) = game.settings().daily {
+        // Daily boards have their own history instead of statistics.
+        let counted = DailyHistory::load().and_then(|mut history| {
+            let counted = history.finish(date, game, &metrics);
+            history.save().map(|_| counted)
+        });
+        match counted {
+            Ok(true) if game.is_practice() || game.hints_used() > 0 => print_string(
+                "Undo or hints were used, so the day is saved as not won.",
+                settings,
+            ),
+            Ok(true) => print_string("Result saved to the daily history.", settings),
+            Ok(false) => print_string("Only the first attempt of the day counts.", settings),
+            Err(error) => print_string(
+                &format!("Could not save the daily result: {error}"),
+                settings,
+            ),
+        }
+        return;
+    }
+    if let Some(id) = game.settings().puzzle {
+        // Puzzles keep their own progress instead of statistics.
+        if game.status() != GameStatus::Won {
+            return;
+        }
+        let solved = PuzzleProgress::load().and_then(|mut progress| {
+            let new = progress.solve(id);
+            progress.save().map(|_| new)
+        });
+        match solved {
+            Ok(true) => print_string("Puzzle solved!", settings),
+            Ok(false) => print_string("Puzzle solved again.", settings),
+            Err(error) => print_string(
+                &format!("Could not save puzzle progress: {error}"),
+                settings,
+            ),
+        }
+        return;
+    }
+    if game.settings().board_file {
+        print_string(
+            "Boards loaded from a file don't count towards statistics.",
+            settings,
+        );
+        return;
+    }
+    let mut stats = match Stats::load() {
+        Ok(stats) => stats,
+        Err(error) => {
+            print_string(&format!("Could not load statistics: {error}"), settings);
+            return;
+        }
+    };
+    let best_time = stats.record(game, &metrics);
+    if let Err(error) = stats.save() {
+        print_string(&format!("Could not save statistics: {error}"), settings);
+    } else if best_time {
+        let time = stats::format_time(game.elapsed().as_millis() as u64);
+        print_string(&format!("New best time: {time}"), settings);
+    }
+}
 pub fn print_string(string: &str, settings: &mut Settings) {
     let mut string_x_pos = settings.board_x_pos as u16;
     if settings.bordered {
         string_x_pos = (string_x_pos as i32 - 1).max(0) as u16;
     }
     if settings.centered {
-        string_x_pos = (string_x_pos as i32 - (string.len() / 2) as i32 + (settings.width * 3) / 2)
+        string_x_pos = (string_x_pos as i32 - (string.len() / 2) as i32
+            + (view_size(settings).0 * 3) / 2)
             .max(0) as u16;
     }
     let mut string_y_pos = settings.str_y_pos as u16;
     if settings.showing_board {
         string_y_pos +=
-            (settings.board_y_pos as i32 + settings.height + settings.bordered as i32) as u16;
+            (settings.board_y_pos as i32 + view_size(settings).1 + settings.bordered as i32) as u16;
     }
     stdout()
         .execute(MoveTo(string_x_pos, string_y_pos))
@@ -560,209 +1406,152 @@ pub fn print_string(string: &str, settings: &mut Settings) {
     settings.str_y_pos += 1;
 }
 
-fn update_cell(board: &Vec<Vec<Cell>>, cell_pos: CellPos, settings: &Settings) {
-    let mut x_pos: u16 = (cell_pos.x * 3) as u16;
-    let mut y_pos: u16 = (cell_pos.y) as u16;
-    x_pos += settings.board_x_pos as u16;
-    y_pos += settings.board_y_pos as u16;
-    let terminal_size = get_terminal_size();
-    if x_pos as i32 >= terminal_size.0 {
-        return;
-    }
-    if y_pos as i32 >= terminal_size.0 {
-        return;
+fn update_cell(board: &Board, cell_pos: CellPos, settings: &Settings) {
+    if move_to_cell(cell_pos, settings) {
+        let (y, x) = (cell_pos.y as usize, cell_pos.x as usize);
+        let odds = MINE_ODDS.lock().unwrap();
+        display_cell(
+            &board[y][x],
+            odds.get(board.index_of(y, x)).copied().flatten(),
+            settings.theme,
+        );
     }
-    stdout().execute(MoveTo(x_pos, y_pos)).unwrap();
-    display_cell(&board[cell_pos.y as usize][cell_pos.x as usize]);
 }
-fn display_cell(cell: &Cell) {
-    let display_string;
-    if !cell.flagged {
-        if cell.hidden {
-            display_string = get_display_string('#', cell.selected);
-        } else if cell.element == '0' {
-            display_string = get_display_string(' ', cell.selected);
-        } else {
-            display_string = get_display_string(cell.element, cell.selected);
-        }
-    } else {
-        display_string = get_display_string('⚑', cell.selected);
+/// Redraws the cells a move changed. A cascade on a big board can change far more cells
+/// than are on screen, so those out of view are skipped before touching the terminal.
+fn update_cells(board: &Board, changed: Vec<CellPos>, settings: &Settings) {
+    let (columns, rows) = view_size(settings);
+    let in_view = |pos: &CellPos| {
+        (settings.camera_x..settings.camera_x + columns).contains(&pos.x)
+            && (settings.camera_y..settings.camera_y + rows).contains(&pos.y)
+    };
+    for pos in changed.into_iter().filter(in_view) {
+        update_cell(board, pos, settings);
     }
-    print!("{display_string}");
 }
-fn get_display_string(character: char, is_selected: bool) -> ANSIGenericString<'static, str> {
-    let board_objects_map: HashMap<char, ANSIGenericString<'static, str>>;
-    if !is_selected {
-        board_objects_map = HashMap::from([
-            ('M', RGB(0, 0, 0).on(White).bold().paint(" ✹ ")),
-            ('1', RGB(6, 3, 255).on(White).bold().paint(" 1 ")),
-            ('2', RGB(3, 122, 6).on(White).bold().paint(" 2 ")),
-            ('3', RGB(254, 0, 0).on(White).bold().paint(" 3 ")),
-            ('4', RGB(0, 0, 132).on(White).bold().paint(" 4 ")),
-            ('5', RGB(130, 1, 2).on(White).bold().paint(" 5 ")),
-            ('6', RGB(2, 127, 130).on(White).bold().paint(" 6 ")),
-            ('7', RGB(0, 0, 0).on(White).bold().paint(" 7 ")),
-            ('8', RGB(125, 125, 125).on(White).bold().paint(" 8 ")),
-            ('#', Black.on(Black).bold().paint("   ")),
-            ('⚑', White.on(Black).bold().paint(" ⚑ ")),
-            (' ', White.on(White).bold().paint("   ")),
-        ]);
-    } else {
-        board_objects_map = HashMap::from([
-            ('M', RGB(0, 0, 0).on(RGB(144, 238, 144)).bold().paint(" ✹ ")),
-            (
-                '1',
-                RGB(6, 3, 255).on(RGB(144, 238, 144)).bold().paint(" 1 "),
-            ),
-            (
-                '2',
-                RGB(3, 122, 6).on(RGB(144, 238, 144)).bold().paint(" 2 "),
-            ),
-            (
-                '3',
-                RGB(254, 0, 0).on(RGB(144, 238, 144)).bold().paint(" 3 "),
-            ),
-            (
-                '4',
-                RGB(0, 0, 132).on(RGB(144, 238, 144)).bold().paint(" 4 "),
-            ),
-            (
-                '5',
-                RGB(130, 1, 2).on(RGB(144, 238, 144)).bold().paint(" 5 "),
-            ),
-            (
-                '6',
-                RGB(2, 127, 130).on(RGB(144, 238, 144)).bold().paint(" 6 "),
-            ),
-            ('7', RGB(0, 0, 0).on(RGB(144, 238, 144)).bold().paint(" 7 ")),
-            (
-                '8',
-                RGB(125, 125, 125)
-                    .on(RGB(144, 238, 144))
-                    .bold()
-                    .paint(" 8 "),
-            ),
-            ('#', Black.on(RGB(144, 238, 144)).bold().paint("   ")),
-            ('⚑', White.on(RGB(144, 238, 144)).bold().paint(" ⚑ ")),
-            (' ', White.on(RGB(144, 238, 144)).bold().paint("   ")),
-        ]);
-    }
-    board_objects_map.get(&character).unwrap().clone()
-}
-fn get_around_cell(
-    coords: [usize; 2],
-    board: &Vec<Vec<Cell>>,
-    settings: &Settings,
-) -> Vec<(char, usize, usize)> {
-    let mut cells: Vec<(char, usize, usize)> = vec![];
-    let iterator = [coords[0] as i32, coords[1] as i32];
-    for i in iterator[0] - 1..=iterator[0] + 1 {
-        for j in iterator[1] - 1..=iterator[1] + 1 {
-            if i >= 0 && j >= 0 && i < settings.height && j < settings.width {
-                cells.push((
-                    board[i as usize][j as usize].element,
-                    i as usize,
-                    j as usize,
-                ));
-            }
-        }
+fn display_hint(hint: Hint, settings: &Settings) {
+    let (cell_pos, display_string) = match hint {
+        Hint::Safe(cell_pos) => (cell_pos, Black.on(RGB(135, 206, 250)).bold().paint(" ✓ ")),
+        Hint::Mine(cell_pos) => (cell_pos, Black.on(RGB(255, 99, 71)).bold().paint(" ⚑ ")),
+    };
+    if move_to_cell(cell_pos, settings) {
+        print!("{display_string}");
     }
-    cells
 }
-fn deobfuscate_board(
-    board: &mut Vec<Vec<Cell>>,
-    cell_pos: CellPos,
-    settings: &Settings,
-    hidden_cells: &mut Vec<(usize, usize)>,
-) {
-    let mut to_check = vec![];
-    if board[cell_pos.y as usize][cell_pos.x as usize].element == '0' {
-        to_check.push((cell_pos.y as usize, cell_pos.x as usize));
-    }
-    let mut next_to_check: Vec<(usize, usize)> = Vec::new();
-    let mut prev_checked: Vec<(usize, usize)> = Vec::new();
-    while !to_check.is_empty() {
-        for i in to_check.iter() {
-            let around = get_around_cell([i.0, i.1], board, settings);
-            for j in around.iter() {
-                let curr_cell = (j.1, j.2);
-                if !prev_checked.contains(&curr_cell) {
-                    prev_checked.push(curr_cell);
-                    if j.0 == '0' {
-                        next_to_check.push(curr_cell);
-                        board[j.1][j.2].hidden = false;
-                        update_cell(
-                            board,
-                            CellPos {
-                                x: j.2 as i32,
-                                y: j.1 as i32,
-                            },
-                            settings,
-                        );
-                        hidden_cells.retain(|value| *value != (j.1, j.2));
-                    } else if j.0 != '0' && j.0 != 'M' {
-                        board[j.1][j.2].hidden = false;
-                        update_cell(
-                            board,
-                            CellPos {
-                                x: j.2 as i32,
-                                y: j.1 as i32,
-                            },
-                            settings,
-                        );
-                        hidden_cells.retain(|value| *value != (j.1, j.2));
-                    }
-                }
-            }
-        }
-        to_check.clone_from(&next_to_check);
-        next_to_check = vec![];
+/// Moves the terminal cursor onto a cell. Returns false when the cell is out of view.
+fn move_to_cell(cell_pos: CellPos, settings: &Settings) -> bool {
+    let (columns, rows) = view_size(settings);
+    let x = cell_pos.x - settings.camera_x;
+    let y = cell_pos.y - settings.camera_y;
+    if x < 0 || y < 0 || x >= columns || y >= rows {
+        return false;
     }
+    let x_pos = (x * 3) as u16 + settings.board_x_pos as u16;
+    let y_pos = y as u16 + settings.board_y_pos as u16;
+    stdout().execute(MoveTo(x_pos, y_pos)).unwrap();
+    true
 }
-fn event(
-    cell_pos: CellPos,
-    board: &mut Vec<Vec<Cell>>,
-    settings: &Settings,
-    hidden_cells: &mut Vec<(usize, usize)>,
-) -> Click {
-    let cell = board[cell_pos.y as usize][cell_pos.x as usize];
-    if cell.flagged {
-        return Click::Fine;
-    }
-    let cell_type = cell.element;
-    if cell_type == 'M' {
-        Click::Dead
-    } else if cell_type != '0' {
-        board[cell_pos.y as usize][cell_pos.x as usize].hidden = false;
-        update_cell(board, cell_pos, settings);
-        hidden_cells.retain(|value| *value != (cell_pos.y as usize, cell_pos.x as usize));
-        Click::Fine
+fn flag_cell(game: &mut Game, cell_pos: CellPos, settings: &Settings) {
+    let changed = game.toggle_flag(cell_pos);
+    update_cells(&game.board, changed, settings);
+    update_probabilities(game, settings);
+}
+/// Tints hidden cells by their chance of being a mine when the overlay is on. Called at
+/// the start of each game too, to clear the odds of the last one.
+fn update_probabilities(game: &Game, settings: &Settings) {
+    let probabilities = if settings.show_probabilities && game.status() == GameStatus::Playing {
+        mine_probabilities(game.board(), game.settings())
     } else {
-        board[cell_pos.y as usize][cell_pos.x as usize].hidden = false;
-        hidden_cells.retain(|value| *value != (cell_pos.y as usize, cell_pos.x as usize));
-        deobfuscate_board(board, cell_pos, settings, hidden_cells);
-        Click::Fine
+        None
+    };
+    let board = game.board();
+    let mut odds = MINE_ODDS.lock().unwrap();
+    let old = std::mem::take(&mut *odds);
+    if let Some(probabilities) = probabilities {
+        *odds = board
+            .cells()
+            .enumerate()
+            .map(|(index, cell)| {
+                (cell.hidden && !cell.is_flagged())
+                    .then(|| probabilities[index / board.width()][index % board.width()])
+            })
+            .collect();
+    }
+    if old.is_empty() && odds.is_empty() {
+        return;
     }
+    let changed: Vec<CellPos> = (0..board.width() * board.height())
+        .filter(|index| odds.get(*index).copied().flatten() != old.get(*index).copied().flatten())
+        .map(|index| CellPos {
+            x: (index % board.width()) as i32,
+            y: (index / board.width()) as i32,
+        })
+        .collect();
+    drop(odds);
+    update_cells(board, changed, settings);
 }
-fn flag(board: &mut Vec<Vec<Cell>>, cell_pos: CellPos, settings: &Settings) {
-    board[cell_pos.y as usize][cell_pos.x as usize].flagged =
-        !board[cell_pos.y as usize][cell_pos.x as usize].flagged;
-    update_cell(board, cell_pos, settings);
+fn display_cell(cell: &Cell, probability: Option<f64>, theme: Theme) {
+    let display_string;
+    if cell.mark == Mark::Flag {
+        display_string = get_display_string('⚑', cell.selected, theme);
+    } else if cell.hidden && cell.mark == Mark::Question {
+        display_string = get_display_string('?', cell.selected, theme);
+    } else if let (true, false, Some(probability)) = (cell.hidden, cell.selected, probability) {
+        let red = (220.0 * probability) as u8;
+        let green = (220.0 * (1.0 - probability)) as u8;
+        display_string = Black.on(RGB(red, green, 40)).bold().paint("   ");
+    } else if cell.hidden {
+        display_string = get_display_string('#', cell.selected, theme);
+    } else if cell.element == '0' {
+        display_string = get_display_string(' ', cell.selected, theme);
+    } else {
+        display_string = get_display_string(cell.element, cell.selected, theme);
+    }
+    print!("{display_string}");
 }
-fn won(hidden_cells: &mut Vec<(usize, usize)>) -> bool {
-    hidden_cells.is_empty()
+fn get_display_string(
+    character: char,
+    is_selected: bool,
+    theme: Theme,
+) -> ANSIGenericString<'static, str> {
+    let palette = theme.palette();
+    let background = |hidden: bool| {
+        if is_selected {
+            RGB(144, 238, 144)
+        } else if hidden {
+            palette.hidden
+        } else {
+            palette.revealed
+        }
+    };
+    match character {
+        'M' => palette.mine.on(background(false)).bold().paint(" ✹ "),
+        '1'..='8' => {
+            let number = character.to_digit(10).unwrap() as usize;
+            palette.numbers[number - 1]
+                .on(background(false))
+                .bold()
+                .paint(format!(" {character} "))
+        }
+        '#' => Black.on(background(true)).bold().paint("   "),
+        '⚑' => White.on(background(true)).bold().paint(" ⚑ "),
+        '?' => White.on(background(true)).bold().paint(" ? "),
+        _ => White.on(background(false)).bold().paint("   "),
+    }
 }
 fn get_appearance_settings(settings: &mut Settings) {
     let appearance_options = vec!["Centered", "Bordered"];
     let defaults = vec![settings.centered, settings.bordered];
-    let mut theme = ColorfulTheme::default();
-    theme.defaults_style = dialoguer::console::Style::new().red();
     let green_style = dialoguer::console::Style::new().green().bold();
     let black_style = dialoguer::console::Style::new().black();
     let checked_item_prefix = green_style.apply_to("✓".to_owned());
     let unchecked_item_prefix = black_style.apply_to("☐".to_owned());
-    theme.checked_item_prefix = checked_item_prefix;
-    theme.unchecked_item_prefix = unchecked_item_prefix;
+    let theme = ColorfulTheme {
+        defaults_style: dialoguer::console::Style::new().red(),
+        checked_item_prefix,
+        unchecked_item_prefix,
+        ..ColorfulTheme::default()
+    };
     let appearance = MultiSelect::with_theme(&theme)
         .items(&appearance_options)
         .defaults(&defaults)
@@ -777,10 +1566,55 @@ fn get_appearance_settings(settings: &mut Settings) {
             _ => {}
         }
     }
+    let themes: Vec<&str> = Theme::ALL.iter().map(Theme::name).collect();
+    let theme = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Theme")
+        .items(&themes)
+        .default(
+            Theme::ALL
+                .iter()
+                .position(|t| *t == settings.theme)
+                .unwrap_or(0),
+        )
+        .interact()
+        .unwrap();
+    settings.theme = Theme::ALL[theme];
     center_board(settings);
 }
+fn get_gameplay_settings(settings: &mut Settings) {
+    let gameplay_options = vec!["No guessing", "Question marks"];
+    let defaults = vec![settings.no_guess, settings.question_marks];
+    let green_style = dialoguer::console::Style::new().green().bold();
+    let black_style = dialoguer::console::Style::new().black();
+    let theme = ColorfulTheme {
+        defaults_style: dialoguer::console::Style::new().red(),
+        checked_item_prefix: green_style.apply_to("✓".to_owned()),
+        unchecked_item_prefix: black_style.apply_to("☐".to_owned()),
+        ..ColorfulTheme::default()
+    };
+    let gameplay = MultiSelect::with_theme(&theme)
+        .items(&gameplay_options)
+        .defaults(&defaults)
+        .interact()
+        .unwrap();
+    settings.no_guess = false;
+    settings.question_marks = false;
+    for i in gameplay {
+        match i {
+            0 => settings.no_guess = true,
+            1 => settings.question_marks = true,
+            _ => {}
+        }
+    }
+}
 fn select_input_type(settings: &mut Settings) {
-    let input_options = vec!["Mouse", "Keyboard"];
+    let input_options = vec![
+        "Mouse",
+        "Keyboard",
+        "Mouse and keyboard",
+        "Mouse buttons",
+        "Keys",
+    ];
     let input_type = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Select Input Type")
         .items(&input_options)
@@ -789,10 +1623,103 @@ fn select_input_type(settings: &mut Settings) {
     let input_type = match input_type {
         0 => InputType::Mouse,
         1 => InputType::Keyboard,
+        2 => InputType::Hybrid,
+        3 => return select_mouse_bindings(settings),
+        4 => return select_key_bindings(settings),
         _ => InputType::Mouse,
     };
     settings.input_type = input_type;
 }
+fn select_mouse_bindings(settings: &mut Settings) {
+    loop {
+        let bindings = settings.mouse_bindings;
+        let button_options = vec![
+            format!("Left button: {}", bindings.left.name()),
+            format!("Middle button: {}", bindings.middle.name()),
+            format!("Right button: {}", bindings.right.name()),
+            "Done".to_owned(),
+        ];
+        let button = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select a mouse button to change")
+            .items(&button_options)
+            .interact()
+            .unwrap();
+        let binding = match button {
+            0 => &mut settings.mouse_bindings.left,
+            1 => &mut settings.mouse_bindings.middle,
+            2 => &mut settings.mouse_bindings.right,
+            _ => break,
+        };
+        let action_options: Vec<&str> = MouseAction::ALL.iter().map(|a| a.name()).collect();
+        let action = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select an action")
+            .items(&action_options)
+            .default(
+                MouseAction::ALL
+                    .iter()
+                    .position(|a| a == binding)
+                    .unwrap_or(0),
+            )
+            .interact()
+            .unwrap();
+        *binding = MouseAction::ALL[action];
+    }
+}
+fn select_key_bindings(settings: &mut Settings) {
+    loop {
+        let bindings = settings.key_bindings;
+        let presets = KeyBindings::presets();
+        let mut key_options: Vec<String> = presets
+            .iter()
+            .map(|(name, _)| format!("Use preset: {name}"))
+            .collect();
+        key_options.extend(
+            KeyAction::ALL
+                .iter()
+                .map(|action| format!("{}: {}", action.name(), bindings.describe(*action))),
+        );
+        key_options.push("Done".to_owned());
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select an action to change")
+            .items(&key_options)
+            .interact()
+            .unwrap();
+        if let Some((_, preset)) = presets.get(choice) {
+            settings.key_bindings = *preset;
+            continue;
+        }
+        let Some(action) = KeyAction::ALL.get(choice - presets.len()) else {
+            break;
+        };
+        println!("Press the new key for {}", action.name());
+        enable_raw_mode().unwrap();
+        let key = loop {
+            if let Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                ..
+            }) = read().unwrap()
+            {
+                if let Some(key) = Key::from_code(code) {
+                    break key;
+                }
+            }
+        };
+        disable_raw_mode().unwrap();
+        if let Some(owner) = settings.key_bindings.would_unbind(key, *action) {
+            println!(
+                "{} is the only key for {}, bind another key to it first.",
+                key.name(),
+                owner.name()
+            );
+            continue;
+        }
+        settings.key_bindings.unbind(*action);
+        if !settings.key_bindings.bind(key, *action) {
+            println!("Too many keys are bound, free some up first.");
+        }
+    }
+}
 fn select_difficulty(settings: &mut Settings) {
     let difficulty_options = vec!["Easy", "Normal", "Hard", "Custom"];
     let difficulty = Select::with_theme(&ColorfulTheme::default())
@@ -807,62 +1734,256 @@ fn select_difficulty(settings: &mut Settings) {
         3 => Difficulty::Custom,
         _ => Difficulty::Easy,
     };
-    match difficulty {
-        Difficulty::Easy => {
-            settings.mines = 10;
-            settings.width = 8;
-            settings.height = 8;
-        }
-        Difficulty::Normal => {
-            settings.mines = 40;
-            settings.width = 16;
-            settings.height = 16;
-        }
-        Difficulty::Hard => {
-            settings.mines = 99;
-            settings.width = 30;
-            settings.height = 16;
-        }
-        Difficulty::Custom => {
-            let size = terminal_size::terminal_size().unwrap();
-            let width: u32 = Input::with_theme(&ColorfulTheme::default())
-                .with_prompt(&format!("Board width (max: {})", size.0 .0 / 3))
-                .validate_with(|x: &u32| {
-                    if *x > size.0 .0 as u32 / 3 {
-                        Err("Width entered exceeds the width of your terminal")
-                    } else {
-                        Ok(())
-                    }
-                })
-                .interact()
-                .unwrap();
-            settings.width = width as i32;
-            let height: u32 = Input::with_theme(&ColorfulTheme::default())
-                .with_prompt(&format!("Board height (max: {})", size.1 .0 -2))
-                .validate_with(|x: &u32| {
-                    if *x > size.1 .0 as u32 - 2 {
-                        Err("Height entered exceeds the height of your terminal and the instructions")
-                    } else {
-                        Ok(())
-                    }
-                })
-                .interact()
-                .unwrap();
-            settings.height = height as i32;
-            let mines: u32 = Input::with_theme(&ColorfulTheme::default())
-                .with_prompt("Mine amount")
-                .validate_with(|x: &u32| {
-                    if *x >= width * height {
-                        Err("Mine amount cannot exceed board area")
+    difficulty.apply(settings);
+    if let Difficulty::Custom = difficulty {
+        let width: u32 = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Board width (max: {MAX_BOARD_SIZE})"))
+            .validate_with(|x: &u32| validate_width(*x))
+            .interact()
+            .unwrap();
+        settings.width = width as i32;
+        let height: u32 = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Board height (max: {MAX_BOARD_SIZE})"))
+            .validate_with(|x: &u32| validate_height(*x))
+            .interact()
+            .unwrap();
+        settings.height = height as i32;
+        let mines: u32 = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Mine amount")
+            .validate_with(|x: &u32| validate_mines(*x, width, height))
+            .interact()
+            .unwrap();
+        settings.mines = mines as i32;
+    }
+}
+/// How often the view scrolls while the mouse rests on its edge.
+const EDGE_SCROLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Largest width or height of a custom board. Boards larger than the terminal scroll.
+const MAX_BOARD_SIZE: u32 = 1000;
+fn validate_width(width: u32) -> Result<(), String> {
+    if width == 0 {
+        Err("Width must be at least 1".to_owned())
+    } else if width > MAX_BOARD_SIZE {
+        Err(format!("Width cannot be more than {MAX_BOARD_SIZE}"))
+    } else {
+        Ok(())
+    }
+}
+fn validate_height(height: u32) -> Result<(), String> {
+    if height == 0 {
+        Err("Height must be at least 1".to_owned())
+    } else if height > MAX_BOARD_SIZE {
+        Err(format!("Height cannot be more than {MAX_BOARD_SIZE}"))
+    } else {
+        Ok(())
+    }
+}
+fn validate_mines(mines: u32, width: u32, height: u32) -> Result<(), String> {
+    if mines >= width * height {
+        Err("Mine amount cannot exceed board area".to_owned())
+    } else {
+        Ok(())
+    }
+}
+fn show_statistics() {
+    match Stats::load() {
+        Ok(stats) if stats.boards.is_empty() => println!("No games played yet."),
+        Ok(stats) => {
+            for (name, board) in stats.boards.iter() {
+                let best_times: Vec<String> = board
+                    .best_times
+                    .iter()
+                    .map(|time| stats::format_time(*time))
+                    .collect();
+                println!(
+                    "{name}: {} won of {} ({:.0}%), streak {} (best {}), best times: {}",
+                    board.games_won,
+                    board.games_played,
+                    board.win_rate() * 100.0,
+                    board.current_streak,
+                    board.best_streak,
+                    if best_times.is_empty() {
+                        "none".to_owned()
                     } else {
-                        Ok(())
+                        best_times.join(", ")
                     }
-                })
-                .interact()
-                .unwrap();
-            settings.mines = mines as i32;
+                );
+                if board.best_three_bv_per_second > 0.0 {
+                    println!(
+                        "  best 3BV/s {:.2}, best IOE {:.2}, best click efficiency {:.0}%",
+                        board.best_three_bv_per_second,
+                        board.best_ioe,
+                        board.best_click_efficiency * 100.0
+                    );
+                }
+            }
+        }
+        Err(error) => println!("Could not load statistics: {error}"),
+    }
+    Select::with_theme(&ColorfulTheme::default())
+        .items(&["Back"])
+        .interact()
+        .unwrap();
+}
+fn select_replay(settings: &mut Settings) {
+    let replays = match list_replays() {
+        Ok(replays) => replays,
+        Err(error) => {
+            println!("Could not list replays: {error}");
+            return;
         }
     };
+    let mut loaded = vec![];
+    let mut options = vec![];
+    for path in replays {
+        match Replay::load(&path) {
+            Ok(replay) => {
+                options.push(replay.describe());
+                loaded.push(replay);
+            }
+            Err(error) => println!("Skipping replay: {error}"),
+        }
+    }
+    if loaded.is_empty() {
+        println!("No replays yet. Every finished game is saved as one.");
+    }
+    options.push("Back".to_owned());
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .items(&options)
+        .default(0)
+        .interact()
+        .unwrap();
+    if let Some(replay) = loaded.get(choice) {
+        play_replay(replay, settings);
+    }
+}
+/// Shows a recorded game on the normal board, in replay time.
+///
+/// The viewer's appearance settings are used; the board itself comes from the replay.
+pub fn play_replay(replay: &Replay, settings: &mut Settings) {
+    const SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+    let mut speed = 1;
+    let mut paused = false;
+    let mut replay_settings = *replay.settings();
+    replay_settings.bordered = settings.bordered;
+    replay_settings.centered = settings.centered;
+    replay_settings.replaying = true;
+    let settings = &mut replay_settings;
+    let moves = replay.moves();
+    let mut game = replay.game();
+    // The game clock starts with the first click, not when the board appeared.
+    let first_click = moves
+        .iter()
+        .find(|mv| mv.kind != MoveKind::Cursor)
+        .map_or(0, |mv| mv.time_ms) as f64;
+    let mut cell_pos = CellPos {
+        x: settings.width / 2,
+        y: settings.height / 2,
+    };
+    game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
+    center_board(settings);
+    center_camera(settings, cell_pos);
+    update_probabilities(&game, settings);
+    display_board(&game.board, settings);
+    enable_raw_mode().unwrap();
+    stdout().execute(Hide).unwrap();
+    let mut replay_ms = 0.0;
+    let mut next = 0;
+    let mut last_tick = Instant::now();
+    loop {
+        if !paused {
+            replay_ms += last_tick.elapsed().as_secs_f64() * 1000.0 * SPEEDS[speed];
+        }
+        last_tick = Instant::now();
+        while next < moves.len() && moves[next].time_ms as f64 <= replay_ms {
+            let mv = &moves[next];
+            // Undo and redo have no cell of their own, so the cursor stays put.
+            let positioned = !matches!(mv.kind, MoveKind::Undo | MoveKind::Redo);
+            if positioned && mv.pos != cell_pos {
+                game.board[cell_pos.y as usize][cell_pos.x as usize].selected = false;
+                update_cell(&game.board, cell_pos, settings);
+                cell_pos = mv.pos;
+                game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
+                if follow_cursor(settings, cell_pos) {
+                    display_board(&game.board, settings);
+                } else {
+                    update_cell(&game.board, cell_pos, settings);
+                }
+            }
+            let changed = game.apply(mv);
+            update_cells(&game.board, changed, settings);
+            next += 1;
+        }
+        let game_ms = if game.status() == GameStatus::NotStarted || next == 0 {
+            0.0
+        } else {
+            let last = moves[next - 1].time_ms as f64;
+            (replay_ms.min(last) - first_click).max(0.0)
+        };
+        let hud = format_hud(&HudState::of(&game), Duration::from_millis(game_ms as u64));
+        let state = if paused { "paused" } else { "" };
+        draw_hud(&format!("{hud}  {}x {state}", SPEEDS[speed]), settings);
+        if next == moves.len() {
+            break;
+        }
+        if !poll(Duration::from_millis(20)).unwrap() {
+            continue;
+        }
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = read().unwrap()
+        {
+            match code {
+                KeyCode::Char(' ') => paused = !paused,
+                KeyCode::Char('n') if paused => replay_ms = moves[next].time_ms as f64,
+                KeyCode::Char(digit @ '1'..='4') => speed = digit as usize - '1' as usize,
+                KeyCode::Esc => {
+                    disable_raw_mode().unwrap();
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+    match game.status() {
+        GameStatus::Won => print_string("You win!", settings),
+        GameStatus::Lost => print_string("You died.", settings),
+        _ => {}
+    }
+    print_string("End of replay. Press any key to go back.", settings);
+    loop {
+        if let Event::Key(KeyEvent {
+            kind: KeyEventKind::Press,
+            ..
+        }) = read().unwrap()
+        {
+            break;
+        }
+    }
+    disable_raw_mode().unwrap();
+    clear(settings);
+}
+fn select_seed(settings: &mut Settings) {
+    let current = settings
+        .seed
+        .map(|seed| seed.to_string())
+        .unwrap_or_default();
+    let seed: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Seed (leave empty for a random board)")
+        .with_initial_text(current)
+        .allow_empty(true)
+        .validate_with(|x: &String| {
+            if x.trim().is_empty() || x.trim().parse::<u64>().is_ok() {
+                Ok(())
+            } else {
+                Err("Seed must be a whole number")
+            }
+        })
+        .interact_text()
+        .unwrap();
+    settings.seed = seed.trim().parse().ok();
 }
 fn exit_gracefully() {
     disable_raw_mode().unwrap();
@@ -872,93 +1993,209 @@ fn exit_gracefully() {
     stdout().execute(Show).unwrap();
     process::exit(0);
 }
-fn reveal_board(board: &mut Vec<Vec<Cell>>, settings: &Settings) {
-    let mut cells_to_update: Vec<(i32, i32)> = vec![];
-    for (x, i) in board.iter_mut().enumerate() {
-        for (y, j) in i.iter_mut().enumerate() {
-            if j.hidden || j.selected {
-                cells_to_update.push((x as i32, y as i32));
-            }
-            j.hidden = false;
-            j.selected = false;
-        }
-    }
-    for cell in cells_to_update {
-        update_cell(
-            board,
-            CellPos {
-                x: cell.1,
-                y: cell.0,
-            },
-            settings,
-        );
-    }
+fn reveal_board(game: &mut Game, settings: &Settings) {
+    let changed = game.reveal_all();
+    update_cells(&game.board, changed, settings);
 }
-fn initialize_free_cells(board: &Vec<Vec<Cell>>) -> Vec<(usize, usize)> {
-    let mut hidden_cells: Vec<(usize, usize)> = vec![];
-    for (row_number, row) in board.iter().enumerate() {
-        for (cell_number, cell) in row.iter().enumerate() {
-            if cell.element != 'M' {
-                hidden_cells.push((row_number, cell_number));
+
+pub fn main_menu(settings: Settings, go_directly_to_game: bool) {
+    run_games(settings, go_directly_to_game, None);
+}
+/// Plays today's daily board, then carries on like the main menu.
+pub fn play_daily(settings: Settings) {
+    let game = start_daily(Daily::today(), &settings);
+    run_games(settings, true, Some(game));
+}
+/// Walks the player through the lessons of the tutorial. Each step waits for the move it
+/// asks for; any other move is taken back. Returns when the player leaves or finishes.
+pub fn play_tutorial(settings: &Settings) {
+    let mut settings = *settings;
+    let menu_settings = settings;
+    let mut gesture = MouseGesture::default();
+    for (number, lesson) in tutorial::LESSONS.iter().enumerate() {
+        let mut game = lesson.game(menu_settings);
+        settings = *game.settings();
+        center_board(&mut settings);
+        let mut cell_pos = CellPos {
+            x: settings.width / 2,
+            y: settings.height / 2,
+        };
+        game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
+        center_camera(&mut settings, cell_pos);
+        update_probabilities(&game, &settings);
+        let title = format!(
+            "Lesson {} of {}: {}",
+            number + 1,
+            tutorial::LESSONS.len(),
+            lesson.title
+        );
+        for step in lesson.steps {
+            let before = (game.clone(), cell_pos);
+            let mut mistake: Option<&str> = None;
+            loop {
+                display_board(&game.board, &mut settings);
+                print_string(&title, &mut settings);
+                for line in lesson.intro {
+                    print_string(line, &mut settings);
+                }
+                if let Some(mistake) = mistake {
+                    print_string(mistake, &mut settings);
+                }
+                print_string(step.instruction, &mut settings);
+                if step.marked || mistake.is_some() {
+                    for pos in step.cells() {
+                        display_hint(
+                            match step.kind {
+                                MoveKind::Flag => Hint::Mine(pos),
+                                _ => Hint::Safe(pos),
+                            },
+                            &settings,
+                        );
+                    }
+                }
+                let settings_mutex = Arc::new(Mutex::new(settings));
+                let (choice, new_cell_pos) = get_choice_from_user(
+                    &mut game,
+                    Arc::clone(&settings_mutex),
+                    cell_pos,
+                    &mut gesture,
+                );
+                cell_pos = new_cell_pos;
+                settings = *settings_mutex.lock().unwrap();
+                match choice {
+                    Choice::Exit => {
+                        clear(&mut settings);
+                        return;
+                    }
+                    Choice::Click | Choice::Chord => {
+                        let cell = game.board[cell_pos.y as usize][cell_pos.x as usize];
+                        if matches!(choice, Choice::Chord) || !cell.hidden {
+                            game.chord(cell_pos);
+                        } else {
+                            game.reveal(cell_pos);
+                        }
+                    }
+                    Choice::Flag => {}
+                }
+                let made: Vec<&Move> = game.moves()[before.0.moves().len()..]
+                    .iter()
+                    .filter(|mv| mv.kind != MoveKind::Cursor)
+                    .collect();
+                if let [mv] = made[..] {
+                    if step.accepts(mv, &game) {
+                        break;
+                    }
+                }
+                mistake = Some(if game.status() == GameStatus::Lost {
+                    "That was a mine! Let's try that again."
+                } else {
+                    "Not quite. The cells to use are marked now."
+                });
+                // Anything else is taken back, keeping the cursor where it is.
+                game = before.0.clone();
+                let old_pos = before.1;
+                game.board[old_pos.y as usize][old_pos.x as usize].selected = false;
+                game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
             }
         }
+        display_board(&game.board, &mut settings);
+        print_string(&title, &mut settings);
+        for line in lesson.outro {
+            print_string(line, &mut settings);
+        }
+        let options: &[&str] = if number + 1 < tutorial::LESSONS.len() {
+            &["Next lesson", "Main Menu"]
+        } else {
+            &["Main Menu"]
+        };
+        let y_pos = settings.board_y_pos
+            + view_size(&settings).1 as u32
+            + settings.str_y_pos
+            + settings.bordered as u32;
+        stdout().execute(MoveTo(0, y_pos as u16)).unwrap();
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .items(options)
+            .interact()
+            .unwrap();
+        if options[choice] == "Main Menu" {
+            break;
+        }
     }
-    hidden_cells
+    clear(&mut settings);
 }
-
-pub fn main_menu(mut settings: Settings, go_directly_to_game: bool) {
+/// Plays `game`, such as one loaded from a board file, then carries on like the main menu.
+pub fn play_game(game: Game) {
+    run_games(*game.settings(), true, Some(game));
+}
+fn run_games(mut settings: Settings, go_directly_to_game: bool, mut first_game: Option<Game>) {
     clear(&mut settings);
     center_board(&mut settings);
+    let mut gesture = MouseGesture::default();
     loop {
-        if !go_directly_to_game {
-            get_settings(&mut settings);
-        }
-        let mut board = vec![
-            vec![
-                Cell {
-                    hidden: true,
-                    element: '0',
-                    flagged: false,
-                    selected: false,
-                };
-                settings.width as usize
-            ];
-            settings.height as usize
-        ];
+        let chosen = if first_game.is_some() {
+            first_game.take()
+        } else if !go_directly_to_game {
+            get_settings(&mut settings)
+        } else {
+            None
+        };
+        // Daily boards and puzzles only change the settings for their own games. A game
+        // passed in by `play_game` brings its own settings, which may name one.
+        let mut menu_settings = settings;
+        menu_settings.daily = None;
+        menu_settings.puzzle = None;
+        menu_settings.board_file = false;
+        let mut game = match chosen {
+            Some(game) => {
+                settings = *game.settings();
+                center_board(&mut settings);
+                game
+            }
+            None => Game::new(settings),
+        };
+        // Puzzles and loaded boards are played again from their start.
+        let restart = (settings.puzzle.is_some() || settings.board_file).then(|| game.restart());
         clear(&mut settings);
         let mut cell_pos = CellPos {
             x: settings.width / 2,
             y: settings.height / 2,
         };
-        board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
-        display_board(&board, &mut settings);
+        game.board[cell_pos.y as usize][cell_pos.x as usize].selected = true;
+        center_camera(&mut settings, cell_pos);
+        update_probabilities(&game, &settings);
+        display_board(&game.board, &mut settings);
         let settings_mutex = Arc::new(Mutex::new(settings));
-        let (mut choice, new_cell_pos) =
-            get_choice_from_user(&mut board, Arc::clone(&settings_mutex), cell_pos);
+        let (mut choice, new_cell_pos) = get_choice_from_user(
+            &mut game,
+            Arc::clone(&settings_mutex),
+            cell_pos,
+            &mut gesture,
+        );
         cell_pos = new_cell_pos;
         settings = *settings_mutex.lock().unwrap();
-        place_mines(&mut board, &settings, cell_pos);
-        place_numbers(&mut board, &settings);
-        let mut hidden_cells = initialize_free_cells(&board);
         loop {
-            if let ControlFlow::Break(_) = game_play_loop_node(
-                &mut board,
-                &mut settings,
-                &choice,
-                cell_pos,
-                &mut hidden_cells,
-            ) {
+            if let ControlFlow::Break(_) =
+                game_play_loop_node(&mut game, &mut settings, &choice, cell_pos, &menu_settings)
+            {
                 break;
             }
-            (choice, cell_pos) =
-                get_choice_from_user(&mut board, Arc::clone(&settings_mutex), cell_pos);
+            *settings_mutex.lock().unwrap() = settings;
+            (choice, cell_pos) = get_choice_from_user(
+                &mut game,
+                Arc::clone(&settings_mutex),
+                cell_pos,
+                &mut gesture,
+            );
+            settings = *settings_mutex.lock().unwrap();
         }
         let terminal_size = get_terminal_size();
         let options = vec!["Play Again", "Main Menu", "Exit"];
         let y_pos;
         if settings.showing_board {
-            y_pos = (settings.board_y_pos + settings.height as u32 + 2 + settings.bordered as u32)
-                as u16;
+            y_pos = (settings.board_y_pos
+                + view_size(&settings).1 as u32
+                + settings.str_y_pos
+                + settings.bordered as u32) as u16;
         } else {
             y_pos = settings.str_y_pos as u16;
             settings.str_y_pos += options.len() as u32;
@@ -972,10 +2209,23 @@ pub fn main_menu(mut settings: Settings, go_directly_to_game: bool) {
             .items(&options)
             .interact()
             .unwrap();
-        match choice {
-            0 => main_menu(settings, true),
-            1 => main_menu(settings, false),
-            2 => exit_gracefully(),
+        let daily = settings.daily.map(Daily::new);
+        match (choice, daily) {
+            (0, Some(daily)) => {
+                let game = start_daily(daily, &menu_settings);
+                run_games(menu_settings, true, Some(game))
+            }
+            (0, None) => match restart {
+                Some(start) => run_games(menu_settings, true, Some(start)),
+                None => main_menu(settings, true),
+            },
+            (1, _)
+                if settings.daily.is_some() || settings.puzzle.is_some() || settings.board_file =>
+            {
+                main_menu(menu_settings, false)
+            }
+            (1, _) => main_menu(settings, false),
+            (2, _) => exit_gracefully(),
             _ => {}
         }
     }
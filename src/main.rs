@@ -1,4 +1,127 @@
-use rustsweeper::{main_menu, Settings};
+use rustsweeper::{
+    load_board_file, main_menu, play_daily, play_game, play_replay, play_tutorial, Replay, Settings,
+};
+use std::{env, io, path::PathBuf, process, str::FromStr};
+
+const USAGE: &str = "Usage: rustsweeper [options]
+
+  --difficulty <easy|normal|hard>  play one of the preset boards
+  --width <n> --height <n> --mines <n>
+                                   play a custom board
+  --seed <n>                       place the mines from this seed
+  --daily                          play today's daily board
+  --tutorial                       learn the rules in a guided tutorial
+  --keyboard                       play with the keyboard instead of the mouse
+  --hybrid                         play with the mouse and keyboard together
+  --no-center                      draw the board in the top left corner
+  --border                         draw a border around the board
+  --load <file>                    play a board file
+  --replay <file>                  watch a replay
+  --help                           show this message
+
+Without options the main menu opens.";
+
 fn main() {
-    main_menu(Settings::default(), false);
-}
\ No newline at end of file
+    let mut settings = Settings::load().unwrap_or_else(|error| {
+        eprintln!("Could not load settings: {error}");
+        eprintln!("Press Enter to continue with the default settings.");
+        io::stdin().read_line(&mut String::new()).ok();
+        Settings::default()
+    });
+    let mut size: [Option<u32>; 3] = [None; 3];
+    let mut board_file: Option<PathBuf> = None;
+    let mut replay_file: Option<PathBuf> = None;
+    let mut daily = false;
+    let mut tutorial = false;
+    let has_args = env::args().len() > 1;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" => {
+                let difficulty: String = value(&arg, args.next());
+                if let Err(error) = settings.set_difficulty(&difficulty) {
+                    fail(&error);
+                }
+            }
+            "--width" => size[0] = Some(value(&arg, args.next())),
+            "--height" => size[1] = Some(value(&arg, args.next())),
+            "--mines" => size[2] = Some(value(&arg, args.next())),
+            "--seed" => settings.set_seed(Some(value(&arg, args.next()))),
+            "--daily" => daily = true,
+            "--tutorial" => tutorial = true,
+            "--keyboard" => settings.set_keyboard(true),
+            "--hybrid" => settings.set_hybrid(),
+            "--no-center" => settings.set_centered(false),
+            "--border" => settings.set_bordered(true),
+            "--load" => board_file = Some(value(&arg, args.next())),
+            "--replay" => replay_file = Some(value(&arg, args.next())),
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => fail(&format!("Unknown argument: {arg}")),
+        }
+    }
+    // Each of these starts something of its own, so only one can be asked for.
+    let modes: Vec<&str> = [
+        ("--load", board_file.is_some()),
+        ("--replay", replay_file.is_some()),
+        ("--daily", daily),
+        ("--tutorial", tutorial),
+    ]
+    .into_iter()
+    .filter_map(|(flag, given)| given.then_some(flag))
+    .collect();
+    if modes.len() > 1 {
+        fail(&format!("{} can't be used together", modes.join(" and ")));
+    }
+    if size.iter().any(Option::is_some) {
+        // Missing dimensions are taken from the chosen difficulty.
+        let width = size[0].unwrap_or(settings.width() as u32);
+        let height = size[1].unwrap_or(settings.height() as u32);
+        let mines = size[2].unwrap_or(settings.mines() as u32);
+        if let Err(error) = settings.set_size(width, height, mines) {
+            fail(&error);
+        }
+    }
+    if let Some(path) = replay_file {
+        match Replay::load(&path) {
+            Ok(replay) => play_replay(&replay, &mut settings),
+            Err(error) => {
+                eprintln!("Could not load the replay: {error}");
+                process::exit(1);
+            }
+        }
+        main_menu(settings, false);
+    }
+    if tutorial {
+        play_tutorial(&settings);
+        main_menu(settings, false);
+    }
+    if daily {
+        play_daily(settings);
+    }
+    if let Some(path) = board_file {
+        match load_board_file(&path, settings) {
+            Ok(game) => play_game(game),
+            Err(error) => {
+                eprintln!("Could not load the board: {error}");
+                process::exit(1);
+            }
+        }
+    }
+    main_menu(settings, has_args);
+}
+
+/// Parses the value following a flag, or exits with a usage error.
+fn value<T: FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        _ => fail(&format!("{flag} expects a valid value")),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    process::exit(2);
+}
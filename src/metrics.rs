@@ -0,0 +1,184 @@
+use crate::{
+    board::BitSet,
+    game::{Game, GameStatus, MoveKind},
+    Board,
+};
+use std::time::Duration;
+
+/// How well a game was played, as shown on the end screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    /// The fewest clicks that clear the board without flags or chords.
+    pub three_bv: u32,
+    /// The part of `three_bv` the player cleared.
+    pub solved_three_bv: u32,
+    pub left_clicks: u32,
+    pub right_clicks: u32,
+    pub chords: u32,
+    /// Clicks that changed something on the board.
+    pub useful_clicks: u32,
+    pub elapsed: Duration,
+}
+impl Metrics {
+    pub fn clicks(&self) -> u32 {
+        self.left_clicks + self.right_clicks + self.chords
+    }
+    pub fn three_bv_per_second(&self) -> f64 {
+        ratio(self.solved_three_bv, self.elapsed.as_secs_f64())
+    }
+    /// Index of efficiency: 3BV cleared per click.
+    pub fn ioe(&self) -> f64 {
+        ratio(self.solved_three_bv, self.clicks() as f64)
+    }
+    /// The share of clicks that changed something, from 0 to 1.
+    pub fn click_efficiency(&self) -> f64 {
+        ratio(self.useful_clicks, self.clicks() as f64)
+    }
+    /// Lines for the end screen.
+    pub fn describe(&self) -> [String; 3] {
+        let three_bv = if self.solved_three_bv == self.three_bv {
+            self.three_bv.to_string()
+        } else {
+            format!("{}/{}", self.solved_three_bv, self.three_bv)
+        };
+        [
+            format!("3BV: {three_bv}  3BV/s: {:.2}", self.three_bv_per_second()),
+            format!(
+                "Clicks: {} left, {} right, {} chords",
+                self.left_clicks, self.right_clicks, self.chords
+            ),
+            format!(
+                "IOE: {:.2}  Click efficiency: {:.0}%",
+                self.ioe(),
+                self.click_efficiency() * 100.0
+            ),
+        ]
+    }
+}
+
+fn ratio(count: u32, total: f64) -> f64 {
+    if total > 0.0 {
+        count as f64 / total
+    } else {
+        0.0
+    }
+}
+
+impl Game {
+    /// Works out the [`Metrics`] of the moves made so far.
+    ///
+    /// The moves are played again on a fresh copy of the board, so the result is the same
+    /// after [`reveal_all`](Game::reveal_all) and for resumed games.
+    pub fn metrics(&self) -> Metrics {
+        let mut replay = self.restart();
+        // What was uncovered before the first move is not the player's work.
+        let (total, already_solved) = three_bv(replay.board());
+        let mut metrics = Metrics {
+            three_bv: total - already_solved,
+            solved_three_bv: 0,
+            left_clicks: 0,
+            right_clicks: 0,
+            chords: 0,
+            useful_clicks: 0,
+            elapsed: self.elapsed(),
+        };
+        if self.status() == GameStatus::NotStarted {
+            return metrics;
+        }
+        for mv in self.moves() {
+            let clicks = match mv.kind {
+                MoveKind::Reveal => &mut metrics.left_clicks,
+                MoveKind::Flag => &mut metrics.right_clicks,
+                MoveKind::Chord => &mut metrics.chords,
+                MoveKind::Cursor | MoveKind::Undo | MoveKind::Redo => {
+                    replay.apply(mv);
+                    continue;
+                }
+            };
+            *clicks += 1;
+            let status = replay.status();
+            if !replay.apply(mv).is_empty() || replay.status() != status {
+                metrics.useful_clicks += 1;
+            }
+        }
+        metrics.solved_three_bv = three_bv(replay.board()).1 - already_solved;
+        metrics
+    }
+}
+
+/// The 3BV of a board with its numbers placed: each opening (a connected area of empty
+/// cells and the numbers around it) counts once, as does each number that touches no
+/// empty cell. Returns the total and the part already uncovered.
+pub fn three_bv(board: &Board) -> (u32, u32) {
+    let (mut total, mut solved) = (0, 0);
+    let mut checked = BitSet::new(board.cells().len());
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let cell = board[y][x];
+            if cell.element != '0' || !checked.insert(board.index_of(y, x)) {
+                continue;
+            }
+            // An opening is uncovered all at once, so one revealed cell means all of it.
+            let mut uncovered = !cell.hidden;
+            let mut to_check = vec![(y, x)];
+            while let Some((y, x)) = to_check.pop() {
+                for (around_y, around_x) in board.neighbours(y, x) {
+                    let around = board[around_y][around_x];
+                    uncovered |= around.element == '0' && !around.hidden;
+                    if around.element == '0' && checked.insert(board.index_of(around_y, around_x)) {
+                        to_check.push((around_y, around_x));
+                    }
+                }
+            }
+            total += 1;
+            solved += uncovered as u32;
+        }
+    }
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let cell = board[y][x];
+            let isolated = !board
+                .neighbours(y, x)
+                .any(|(around_y, around_x)| board[around_y][around_x].element == '0');
+            if matches!(cell.element, '1'..='8') && isolated {
+                total += 1;
+                solved += !cell.hidden as u32;
+            }
+        }
+    }
+    (total, solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board, CellPos, Settings};
+
+    #[test]
+    fn three_bv_counts_openings_once() {
+        assert_eq!(three_bv(&parse_board("...\n...\n").unwrap()), (1, 0));
+        assert_eq!(three_bv(&parse_board("*...\n").unwrap()), (1, 0));
+        assert_eq!(three_bv(&parse_board("...*...\n").unwrap()), (2, 0));
+    }
+
+    #[test]
+    fn three_bv_counts_lone_numbers() {
+        assert_eq!(three_bv(&parse_board("*.\n..\n").unwrap()), (3, 0));
+        assert_eq!(three_bv(&parse_board(".*1\n").unwrap()), (2, 1));
+        assert_eq!(three_bv(&parse_board("0000\n111.\n.*..\n").unwrap()).1, 1);
+    }
+
+    #[test]
+    fn metrics_count_clicks_and_solved_three_bv() {
+        let board = parse_board(".*.\n...\n").unwrap();
+        let mut game = Game::from_board(board, Settings::new(3, 2, 1));
+        game.reveal(CellPos { x: 0, y: 0 });
+        game.toggle_flag(CellPos { x: 1, y: 0 });
+        game.reveal(CellPos { x: 0, y: 0 });
+        let metrics = game.metrics();
+        assert_eq!(metrics.three_bv, 5);
+        assert_eq!(metrics.solved_three_bv, 1);
+        assert_eq!((metrics.left_clicks, metrics.right_clicks), (2, 1));
+        assert_eq!(metrics.useful_clicks, 2);
+    }
+}
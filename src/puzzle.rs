@@ -0,0 +1,164 @@
+//! Puzzles are boards in the text format (see [`crate::parse_board`]) whose revealed cells
+//! and flags are the starting position. A pack is a directory of them, played in file
+//! name order.
+use crate::{
+    data_path, dump_board, game::Game, load_toml, parse_board, save_toml, solver::solves, Board,
+    GameStatus, Settings,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+const PROGRESS_FILE: &str = "puzzles.toml";
+
+/// One puzzle, checked to be solvable from its start without guessing.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    name: String,
+    id: u64,
+    board: Board,
+}
+impl Puzzle {
+    /// Reads and checks the puzzle in `path`. Errors say why it can't be played.
+    pub fn load(path: &Path) -> io::Result<Puzzle> {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let board = fs::read_to_string(path)
+            .and_then(|text| parse_board(&text))
+            .and_then(|board| {
+                check(&board)
+                    .map(|_| board)
+                    .map_err(|message| io::Error::new(ErrorKind::InvalidData, message))
+            })
+            .map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+            })?;
+        Ok(Puzzle {
+            name,
+            id: puzzle_id(&board),
+            board,
+        })
+    }
+    /// The file name without its extension.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Identifies the puzzle by its contents, so renaming or moving the file keeps its
+    /// progress.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// A new game at the start of this puzzle. Its size comes from the puzzle, everything
+    /// else from `settings`.
+    pub fn game(&self, mut settings: Settings) -> Game {
+        settings.width = self.board.width() as i32;
+        settings.height = self.board.height() as i32;
+        settings.mines = mine_count(&self.board);
+        settings.seed = None;
+        settings.daily = None;
+        settings.puzzle = Some(self.id);
+        Game::from_board(self.board.clone(), settings)
+    }
+}
+
+/// Makes sure a puzzle can be finished by logic alone from its start, which also means
+/// that its mines are the only layout that fits what is shown.
+fn check(board: &Board) -> Result<(), String> {
+    for (y, row) in board.rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if cell.is_flagged() && cell.element != 'M' {
+                return Err(format!("the flag at {}, {} is not on a mine", x + 1, y + 1));
+            }
+        }
+    }
+    let settings = Settings::new(
+        board.width() as i32,
+        board.height() as i32,
+        mine_count(board),
+    );
+    let mut game = Game::from_board(board.clone(), settings);
+    if game.status() == GameStatus::Won {
+        return Err("every safe cell is already revealed".to_owned());
+    }
+    if !solves(&mut game, None) {
+        return Err("it can't be solved without guessing".to_owned());
+    }
+    Ok(())
+}
+
+fn mine_count(board: &Board) -> i32 {
+    board.cells().filter(|cell| cell.element == 'M').count() as i32
+}
+
+/// FNV-1a of the puzzle as text, which doesn't change between Rust versions like the
+/// standard hasher may.
+fn puzzle_id(board: &Board) -> u64 {
+    dump_board(board)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// The puzzles of a directory, with the files that could not be used.
+#[derive(Debug, Clone, Default)]
+pub struct PuzzlePack {
+    pub puzzles: Vec<Puzzle>,
+    /// Why each rejected file was left out.
+    pub rejected: Vec<String>,
+}
+impl PuzzlePack {
+    /// Loads every file in `dir` that does not start with a dot, in file name order.
+    pub fn load(dir: &Path) -> io::Result<PuzzlePack> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {error}", dir.display())))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && !path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            })
+            .collect();
+        paths.sort();
+        let mut pack = PuzzlePack::default();
+        for path in paths {
+            match Puzzle::load(&path) {
+                Ok(puzzle) => pack.puzzles.push(puzzle),
+                Err(error) => pack.rejected.push(error.to_string()),
+            }
+        }
+        Ok(pack)
+    }
+}
+
+/// Which puzzles have been solved, stored in `puzzles.toml` under the user's data
+/// directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PuzzleProgress {
+    /// The directory of the pack played last.
+    pub last_pack: Option<String>,
+    /// [`Puzzle::id`]s in hexadecimal.
+    solved: BTreeSet<String>,
+}
+impl PuzzleProgress {
+    /// Reads the progress file. A missing file means nothing is solved yet.
+    pub fn load() -> io::Result<PuzzleProgress> {
+        Ok(load_toml(&data_path(PROGRESS_FILE)?)?.unwrap_or_default())
+    }
+    pub fn save(&self) -> io::Result<()> {
+        save_toml(&data_path(PROGRESS_FILE)?, self)
+    }
+    pub fn is_solved(&self, id: u64) -> bool {
+        self.solved.contains(&format!("{id:016x}"))
+    }
+    /// Marks a puzzle as solved. Returns false if it already was.
+    pub fn solve(&mut self, id: u64) -> bool {
+        self.solved.insert(format!("{id:016x}"))
+    }
+}
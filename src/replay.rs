@@ -0,0 +1,214 @@
+use crate::{
+    board_name, data_path,
+    game::{Game, Move, StartState},
+    load_toml, save_toml, Board, Cell, CellPos, GameStatus, Settings,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const REPLAY_DIR: &str = "replays";
+/// How many replays are kept in the data directory before the oldest are removed.
+const REPLAYS_KEPT: usize = 50;
+
+/// A finished game: its mine layout and every input made, with timestamps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    settings: Settings,
+    /// As text, like the seed of a saved game.
+    seed: String,
+    won: bool,
+    mines: Vec<CellPos>,
+    #[serde(default)]
+    start: StartState,
+    moves: Vec<Move>,
+}
+impl Replay {
+    /// Records `game`. Returns `None` when no mines were placed yet.
+    pub fn from_game(game: &Game) -> Option<Replay> {
+        if game.status() == GameStatus::NotStarted {
+            return None;
+        }
+        let mut mines = vec![];
+        for (y, row) in game.board().rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.element() == 'M' {
+                    mines.push(CellPos {
+                        x: x as i32,
+                        y: y as i32,
+                    });
+                }
+            }
+        }
+        Some(Replay {
+            settings: *game.settings(),
+            seed: game.seed().to_string(),
+            won: game.status() == GameStatus::Won,
+            mines,
+            start: game.start().clone(),
+            moves: game.moves().to_vec(),
+        })
+    }
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let replay: Replay = load_toml(path)?.ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("{} does not exist", path.display()),
+            )
+        })?;
+        let settings = &replay.settings;
+        let in_bounds = |pos: &CellPos| {
+            pos.x >= 0 && pos.y >= 0 && pos.x < settings.width && pos.y < settings.height
+        };
+        if settings.width < 1
+            || settings.height < 1
+            || !replay.mines.iter().all(in_bounds)
+            || !replay.start.revealed.iter().all(in_bounds)
+            || !replay.start.flagged.iter().all(in_bounds)
+            || !replay.moves.iter().all(|mv| in_bounds(&mv.pos))
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has cells outside its board", path.display()),
+            ));
+        }
+        Ok(replay)
+    }
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_toml(path, self)
+    }
+    /// A fresh game with the recorded mines placed, ready for [`Game::apply`].
+    pub fn game(&self) -> Game {
+        let mut settings = self.settings;
+        settings.seed = self.seed.parse().ok();
+        let mut board = Board::new(
+            settings.width as usize,
+            settings.height as usize,
+            Cell::new('0'),
+        );
+        for pos in self.mines.iter() {
+            board[pos.y as usize][pos.x as usize].element = 'M';
+        }
+        self.start.apply(&mut board);
+        Game::from_board(board, settings)
+    }
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+    /// A one line summary for menus, like "Easy, won after 12.345s".
+    pub fn describe(&self) -> String {
+        let last = self.moves.last().map_or(0, |mv| mv.time_ms);
+        format!(
+            "{}, {} after {}",
+            board_name(&self.settings),
+            if self.won { "won" } else { "lost" },
+            crate::stats::format_time(last)
+        )
+    }
+}
+
+/// Stores the replay of a finished game in the data directory, dropping the oldest
+/// replays beyond [`REPLAYS_KEPT`]. Returns where it was written.
+pub fn save_replay(game: &Game) -> io::Result<Option<PathBuf>> {
+    let Some(replay) = Replay::from_game(game) else {
+        return Ok(None);
+    };
+    let dir = data_path(REPLAY_DIR)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+    let path = dir.join(format!("{millis}.toml"));
+    replay.save(&path)?;
+    for old in list_replays()?.iter().skip(REPLAYS_KEPT) {
+        fs::remove_file(old)?;
+    }
+    Ok(Some(path))
+}
+
+/// Replays in the data directory, newest first.
+pub fn list_replays() -> io::Result<Vec<PathBuf>> {
+    let dir = data_path(REPLAY_DIR)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+    let mut replays = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            replays.push(path);
+        }
+    }
+    // File names are creation times in milliseconds, so sort them as numbers.
+    replays.sort_by_key(|path| {
+        std::cmp::Reverse(
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u128>().ok())
+                .unwrap_or(0),
+        )
+    });
+    Ok(replays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dump_board, parse_board};
+
+    /// Saves `game` as a replay and plays it back on a fresh board.
+    fn play_back(game: &Game, name: &str) -> Game {
+        let path = std::env::temp_dir().join(format!(
+            "rustsweeper-test-{}-{name}.toml",
+            std::process::id()
+        ));
+        Replay::from_game(game).unwrap().save(&path).unwrap();
+        let replay = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        let replay = replay.unwrap();
+        let mut played = replay.game();
+        for mv in replay.moves() {
+            played.apply(mv);
+        }
+        played
+    }
+
+    #[test]
+    fn replays_play_back_the_same_game() {
+        let mut settings = Settings::new(9, 9, 10);
+        settings.set_seed(Some(u64::MAX));
+        let mut game = Game::new(settings);
+        assert!(Replay::from_game(&game).is_none());
+        game.reveal(CellPos { x: 4, y: 4 });
+        game.record_cursor(CellPos { x: 8, y: 8 });
+        game.toggle_flag(CellPos { x: 8, y: 8 });
+        game.undo();
+        game.redo();
+        game.reveal(CellPos { x: 0, y: 8 });
+        let played = play_back(&game, "seeded");
+        assert_eq!(played.seed(), u64::MAX);
+        assert_eq!(played.status(), game.status());
+        assert_eq!(dump_board(played.board()), dump_board(game.board()));
+    }
+
+    #[test]
+    fn replays_keep_the_start() {
+        let board = parse_board("2*.\nF2.\n...\n").unwrap();
+        let mut game = Game::from_board(board, Settings::new(3, 3, 2));
+        game.reveal(CellPos { x: 2, y: 0 });
+        let played = play_back(&game, "start");
+        assert_eq!(played.start(), game.start());
+        assert_eq!(dump_board(played.board()), dump_board(game.board()));
+    }
+}
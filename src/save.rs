@@ -0,0 +1,210 @@
+use crate::{
+    data_path,
+    game::{Game, Move, StartState},
+    load_toml, save_toml, Board, CellPos, GameStatus, Settings,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    time::Duration,
+};
+
+const SAVE_FILE: &str = "save.toml";
+
+/// Everything needed to put an unfinished game back exactly as it was left.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedGame {
+    settings: Settings,
+    /// As text, see [`save_toml`].
+    seed: String,
+    elapsed_ms: u64,
+    hints_used: u32,
+    board: Board,
+    #[serde(default)]
+    moves: Vec<Move>,
+    #[serde(default)]
+    practice: bool,
+    /// The date of the daily board, if this is one.
+    #[serde(default)]
+    daily: Option<String>,
+    /// The puzzle id in hexadecimal, if this is a puzzle.
+    #[serde(default)]
+    puzzle: Option<String>,
+    /// Whether the board was loaded from a file.
+    #[serde(default)]
+    board_file: bool,
+    #[serde(default)]
+    start: StartState,
+}
+
+impl SavedGame {
+    fn of(game: &Game) -> SavedGame {
+        SavedGame {
+            settings: *game.settings(),
+            seed: game.seed().to_string(),
+            elapsed_ms: game.elapsed().as_millis() as u64,
+            hints_used: game.hints_used(),
+            board: game.board().clone(),
+            moves: game.moves().to_vec(),
+            practice: game.is_practice(),
+            daily: game.settings().daily.map(|date| date.to_string()),
+            puzzle: game.settings().puzzle.map(|id| format!("{id:016x}")),
+            board_file: game.settings().board_file,
+            start: game.start().clone(),
+        }
+    }
+    /// Checks a save read from `path` and puts its game back.
+    fn into_game(self, path: &Path) -> io::Result<Game> {
+        let size_matches = self.board.height() == self.settings.height as usize
+            && self.board.width() == self.settings.width as usize;
+        if !size_matches {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} does not match its board size", path.display()),
+            ));
+        }
+        let seed = self.seed.parse().map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has an invalid seed {:?}", path.display(), self.seed),
+            )
+        })?;
+        if let Some(cell) = self
+            .board
+            .cells()
+            .find(|cell| cell.element != 'M' && !cell.element.is_ascii_digit())
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has an unknown cell {:?}", path.display(), cell.element),
+            ));
+        }
+        let mut settings = self.settings;
+        if let Some(date) = &self.daily {
+            settings.daily = Some(date.parse().map_err(|_| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} has an invalid date {date:?}", path.display()),
+                )
+            })?);
+        }
+        if let Some(id) = &self.puzzle {
+            settings.puzzle = Some(u64::from_str_radix(id, 16).map_err(|_| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} has an invalid puzzle id {id:?}", path.display()),
+                )
+            })?);
+        }
+        settings.board_file = self.board_file;
+        let in_bounds = |pos: &CellPos| {
+            pos.x >= 0 && pos.y >= 0 && pos.x < settings.width && pos.y < settings.height
+        };
+        if !self
+            .start
+            .revealed
+            .iter()
+            .chain(&self.start.flagged)
+            .all(in_bounds)
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has cells outside its board", path.display()),
+            ));
+        }
+        let game = Game::restore(
+            self.board,
+            settings,
+            seed,
+            self.hints_used,
+            Duration::from_millis(self.elapsed_ms),
+            self.moves,
+            self.practice,
+        );
+        Ok(game.with_start(self.start))
+    }
+}
+
+/// Writes `game` to the save file, replacing any earlier save.
+/// Games that have not started or are already over are not saved.
+pub fn save_game(game: &Game) -> io::Result<()> {
+    if game.status() != GameStatus::Playing {
+        return Ok(());
+    }
+    save_toml(&data_path(SAVE_FILE)?, &SavedGame::of(game))
+}
+
+/// Reads the saved game, if there is one.
+pub fn load_game() -> io::Result<Option<Game>> {
+    let path = data_path(SAVE_FILE)?;
+    let Some(saved) = load_toml::<SavedGame>(&path)? else {
+        return Ok(None);
+    };
+    saved.into_game(&path).map(Some)
+}
+
+pub fn has_saved_game() -> bool {
+    data_path(SAVE_FILE).is_ok_and(|path| path.exists())
+}
+
+pub fn delete_saved_game() -> io::Result<()> {
+    match fs::remove_file(data_path(SAVE_FILE)?) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dump_board, parse_board};
+
+    fn round_trip(game: &Game) -> io::Result<Game> {
+        let contents = toml::to_string(&SavedGame::of(game)).unwrap();
+        let saved: SavedGame = toml::from_str(&contents).unwrap();
+        saved.into_game(Path::new("save.toml"))
+    }
+
+    #[test]
+    fn saved_games_come_back_as_they_were() {
+        let mut settings = Settings::new(9, 9, 10);
+        settings.set_seed(Some(u64::MAX));
+        settings.puzzle = Some(u64::MAX);
+        let mut game = Game::new(settings);
+        game.reveal(CellPos { x: 4, y: 4 });
+        game.toggle_flag(CellPos { x: 0, y: 0 });
+        game.undo();
+        let restored = round_trip(&game).unwrap();
+        assert_eq!(restored.seed(), u64::MAX);
+        assert_eq!(restored.settings().puzzle, Some(u64::MAX));
+        assert_eq!(restored.status(), GameStatus::Playing);
+        assert!(restored.is_practice());
+        assert_eq!(restored.moves(), game.moves());
+        assert_eq!(dump_board(restored.board()), dump_board(game.board()));
+    }
+
+    #[test]
+    fn saved_games_keep_the_start() {
+        let board = parse_board("2*.\nF2.\n...\n").unwrap();
+        let mut settings = Settings::new(3, 3, 2);
+        settings.board_file = true;
+        let mut game = Game::from_board(board, settings);
+        game.reveal(CellPos { x: 2, y: 0 });
+        let restored = round_trip(&game).unwrap();
+        assert!(restored.settings().board_file);
+        assert_eq!(dump_board(restored.board()), dump_board(game.board()));
+        assert_eq!(restored.start(), game.start());
+        assert_eq!(dump_board(&restored.restart().board), "2*.\nF2.\n...\n");
+    }
+
+    #[test]
+    fn saves_of_another_size_are_rejected() {
+        let mut game = Game::from_board(parse_board("1*.\n").unwrap(), Settings::new(3, 1, 1));
+        game.reveal(CellPos { x: 2, y: 0 });
+        let mut saved = SavedGame::of(&game);
+        saved.settings.width = 4;
+        assert!(saved.into_game(Path::new("save.toml")).is_err());
+    }
+}
@@ -0,0 +1,627 @@
+use crate::{
+    game::{CellPos, Game, GameStatus},
+    Board, Settings,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// Frontier groups that need more backtracking steps than this are left undecided.
+const MAX_ENUMERATION_STEPS: usize = 200_000;
+/// How long [`mine_probabilities`] may take. The overlay is worked out again after every
+/// move, so a huge frontier turns it off instead of freezing the game.
+pub const PROBABILITY_TIME_LIMIT: Duration = Duration::from_millis(250);
+
+/// Cells that can be proven safe or proven to be mines from what the player can see.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deductions {
+    pub safe: Vec<CellPos>,
+    pub mines: Vec<CellPos>,
+}
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// One cell worth looking at, as given by the hint command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hint {
+    Safe(CellPos),
+    Mine(CellPos),
+}
+
+/// A revealed number: exactly `mines` of `cells` are mines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Constraint {
+    cells: Vec<(usize, usize)>,
+    mines: i32,
+}
+
+/// Every way of placing mines in a group of frontier cells that uses `mines` mines.
+#[derive(Debug, Clone)]
+struct Tally {
+    mines: usize,
+    solutions: f64,
+    cell_mines: Vec<f64>,
+}
+
+/// Frontier cells that share numbers, so their mines have to be placed together.
+#[derive(Debug, Clone, Default)]
+struct Component {
+    cells: Vec<(usize, usize)>,
+    constraints: Vec<(Vec<usize>, i32)>,
+}
+
+/// Finds certain safe cells and certain mines using only the visible state of the board:
+/// revealed numbers, flags and the total mine count. Flags are trusted to be correct.
+///
+/// Single-number and subset rules are tried first. When they find nothing, every mine
+/// layout of the frontier is enumerated instead.
+pub fn deduce(board: &Board, settings: &Settings) -> Deductions {
+    deduce_until(board, settings, None).unwrap_or_default()
+}
+
+/// Whether `deadline` has passed. `None` never does.
+fn timed_out(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// [`deduce`] that gives up once `deadline` passes, returning `None`. Large boards can
+/// take a long time for a single pass.
+fn deduce_until(
+    board: &Board,
+    settings: &Settings,
+    deadline: Option<Instant>,
+) -> Option<Deductions> {
+    let constraints = get_constraints(board, deadline)?;
+    let mut safe: Vec<(usize, usize)> = vec![];
+    let mut mines: Vec<(usize, usize)> = vec![];
+    for constraint in constraints.iter() {
+        if constraint.mines == 0 {
+            safe.extend(constraint.cells.iter());
+        } else if constraint.mines == constraint.cells.len() as i32 {
+            mines.extend(constraint.cells.iter());
+        }
+    }
+    // A number can only lie inside another that shares its cells, so each is compared
+    // with the others around its first cell.
+    let mut by_cell: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for (y, x) in constraint.cells.iter() {
+            by_cell
+                .entry(board.index_of(*y, *x))
+                .or_default()
+                .push(index);
+        }
+    }
+    for a in constraints.iter() {
+        if timed_out(deadline) {
+            return None;
+        }
+        let (y, x) = a.cells[0];
+        for b in by_cell[&board.index_of(y, x)]
+            .iter()
+            .map(|b| &constraints[*b])
+        {
+            if a == b || a.cells.len() >= b.cells.len() {
+                continue;
+            }
+            if !a.cells.iter().all(|cell| b.cells.contains(cell)) {
+                continue;
+            }
+            let rest: Vec<(usize, usize)> = b
+                .cells
+                .iter()
+                .filter(|cell| !a.cells.contains(cell))
+                .copied()
+                .collect();
+            let rest_mines = b.mines - a.mines;
+            if rest_mines == 0 {
+                safe.extend(rest.iter());
+            } else if rest_mines == rest.len() as i32 {
+                mines.extend(rest.iter());
+            }
+        }
+    }
+    let (unknown, flagged) = count_unknown(board);
+    let mines_left = settings.mines - flagged;
+    if !unknown.is_empty() {
+        if mines_left == 0 {
+            safe.extend(unknown.iter());
+        } else if mines_left == unknown.len() as i32 {
+            mines.extend(unknown.iter());
+        }
+    }
+    if safe.is_empty() && mines.is_empty() {
+        enumerate_frontier(
+            board,
+            &constraints,
+            unknown.len(),
+            mines_left,
+            &mut safe,
+            &mut mines,
+            deadline,
+        );
+    }
+    if timed_out(deadline) {
+        return None;
+    }
+    Some(Deductions {
+        safe: to_positions(safe),
+        mines: to_positions(mines),
+    })
+}
+
+/// Picks one deduced cell for the player, preferring safe cells over mines.
+pub fn hint(board: &Board, settings: &Settings) -> Option<Hint> {
+    let deductions = deduce(board, settings);
+    if let Some(pos) = deductions.safe.first() {
+        Some(Hint::Safe(*pos))
+    } else {
+        deductions.mines.first().map(|pos| Hint::Mine(*pos))
+    }
+}
+
+/// The exact chance that each cell is a mine, given what the player can see.
+///
+/// Every frontier layout is weighted by the number of ways the remaining mines fit in the
+/// cells away from the frontier. Revealed cells are `0.0` and flagged cells `1.0`.
+/// Returns `None` when the frontier is too large to enumerate or the odds take longer than
+/// [`PROBABILITY_TIME_LIMIT`].
+pub fn mine_probabilities(board: &Board, settings: &Settings) -> Option<Vec<Vec<f64>>> {
+    let deadline = Some(Instant::now() + PROBABILITY_TIME_LIMIT);
+    let constraints = get_constraints(board, deadline)?;
+    let (unknown, flagged) = count_unknown(board);
+    let mines_left = settings.mines - flagged;
+    let components = get_components(board, &constraints);
+    let mut all_tallies: Vec<Vec<Tally>> = vec![];
+    for component in components.iter() {
+        if timed_out(deadline) {
+            return None;
+        }
+        let mut tallies = enumerate(component)?;
+        // Only the ratios matter, so keep the counts small.
+        let largest = tallies.iter().map(|t| t.solutions).fold(0.0, f64::max);
+        for tally in tallies.iter_mut() {
+            tally.solutions /= largest;
+            tally
+                .cell_mines
+                .iter_mut()
+                .for_each(|count| *count /= largest);
+        }
+        all_tallies.push(tallies);
+    }
+    let frontier: usize = components.iter().map(|c| c.cells.len()).sum();
+    let outside = unknown.len() - frontier;
+    // Relative number of ways to place `mines_left - k` mines outside the frontier, as a
+    // logarithm. Each is worked out from the one before, as C(n, r) = C(n, r + 1) * (r + 1)
+    // / (n - r).
+    let mut ln_ways: Vec<Option<f64>> = vec![None; frontier + 1];
+    let mut ln = None;
+    for (k, ln_way) in ln_ways.iter_mut().enumerate() {
+        let rest = mines_left - k as i32;
+        if rest < 0 {
+            break;
+        }
+        if rest as usize > outside {
+            continue;
+        }
+        let next = ln.map_or(0.0, |ln: f64| {
+            ln + ((rest + 1) as f64).ln() - ((outside - rest as usize) as f64).ln()
+        });
+        ln = Some(next);
+        *ln_way = ln;
+    }
+    let max_ln_ways = ln_ways.iter().flatten().copied().fold(f64::MIN, f64::max);
+    let ways: Vec<f64> = ln_ways
+        .iter()
+        .map(|w| w.map_or(0.0, |w| (w - max_ln_ways).exp()))
+        .collect();
+
+    let total = convolve(&all_tallies, None, deadline)?;
+    let weight: f64 = total.iter().enumerate().map(|(k, w)| w * ways[k]).sum();
+    if weight == 0.0 {
+        return None;
+    }
+    let mut probabilities: Vec<Vec<f64>> = board
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(|cell| if cell.is_flagged() { 1.0 } else { 0.0 })
+                .collect()
+        })
+        .collect();
+    if outside > 0 {
+        let outside_mines: f64 = total
+            .iter()
+            .enumerate()
+            .map(|(k, w)| w * ways[k] * (mines_left - k as i32).max(0) as f64)
+            .sum();
+        for (y, x) in unknown.iter() {
+            probabilities[*y][*x] = outside_mines / weight / outside as f64;
+        }
+    }
+    for (index, component) in components.iter().enumerate() {
+        let rest = convolve(&all_tallies, Some(index), deadline)?;
+        for (i, (y, x)) in component.cells.iter().enumerate() {
+            let mut cell_weight = 0.0;
+            for tally in all_tallies[index].iter() {
+                for (k, w) in rest.iter().enumerate() {
+                    if let Some(ways) = ways.get(k + tally.mines) {
+                        cell_weight += tally.cell_mines[i] * w * ways;
+                    }
+                }
+            }
+            probabilities[*y][*x] = cell_weight / weight;
+        }
+    }
+    Some(probabilities)
+}
+
+/// Combines the groups into total weights indexed by mine count, optionally leaving one out.
+/// Returns `None` once `deadline` passes.
+fn convolve(
+    all_tallies: &[Vec<Tally>],
+    skip: Option<usize>,
+    deadline: Option<Instant>,
+) -> Option<Vec<f64>> {
+    let mut total = vec![1.0];
+    for (index, tallies) in all_tallies.iter().enumerate() {
+        if Some(index) == skip {
+            continue;
+        }
+        if timed_out(deadline) {
+            return None;
+        }
+        let largest = tallies.iter().map(|t| t.mines).max().unwrap_or(0);
+        let mut next = vec![0.0; total.len() + largest];
+        for (k, w) in total.iter().enumerate() {
+            for tally in tallies.iter() {
+                next[k + tally.mines] += w * tally.solutions;
+            }
+        }
+        total = next;
+    }
+    Some(total)
+}
+
+fn enumerate_frontier(
+    board: &Board,
+    constraints: &[Constraint],
+    unknown: usize,
+    mines_left: i32,
+    safe: &mut Vec<(usize, usize)>,
+    mines: &mut Vec<(usize, usize)>,
+    deadline: Option<Instant>,
+) {
+    let components = get_components(board, constraints);
+    for component in components.iter() {
+        if timed_out(deadline) {
+            return;
+        }
+        let Some(tallies) = enumerate(component) else {
+            continue;
+        };
+        // Any layout of this group has to leave a mine count the rest of the board can hold.
+        let others = unknown - component.cells.len();
+        let possible: Vec<&Tally> = tallies
+            .iter()
+            .filter(|t| {
+                t.mines as i32 <= mines_left && mines_left - t.mines as i32 <= others as i32
+            })
+            .collect();
+        if possible.is_empty() {
+            continue;
+        }
+        let solutions: f64 = possible.iter().map(|t| t.solutions).sum();
+        for (i, cell) in component.cells.iter().enumerate() {
+            let cell_mines: f64 = possible.iter().map(|t| t.cell_mines[i]).sum();
+            if cell_mines == 0.0 {
+                safe.push(*cell);
+            } else if cell_mines == solutions {
+                mines.push(*cell);
+            }
+        }
+    }
+}
+
+/// Splits the frontier into groups of cells that share numbers. Numbers that share a cell
+/// are joined with union-find, keyed by [`Board::index_of`].
+fn get_components(board: &Board, constraints: &[Constraint]) -> Vec<Component> {
+    let mut parent: Vec<usize> = (0..constraints.len()).collect();
+    let mut first_constraint: HashMap<usize, usize> = HashMap::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for (y, x) in constraint.cells.iter() {
+            let other = *first_constraint
+                .entry(board.index_of(*y, *x))
+                .or_insert(index);
+            let (a, b) = (find_root(&mut parent, index), find_root(&mut parent, other));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+    let mut components: Vec<Component> = vec![];
+    let mut component_of: HashMap<usize, usize> = HashMap::new();
+    let mut position_of: HashMap<usize, usize> = HashMap::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        let root = find_root(&mut parent, index);
+        let component = *component_of.entry(root).or_insert_with(|| {
+            components.push(Component::default());
+            components.len() - 1
+        });
+        let component = &mut components[component];
+        let cells = constraint
+            .cells
+            .iter()
+            .map(|(y, x)| {
+                *position_of
+                    .entry(board.index_of(*y, *x))
+                    .or_insert_with(|| {
+                        component.cells.push((*y, *x));
+                        component.cells.len() - 1
+                    })
+            })
+            .collect();
+        component.constraints.push((cells, constraint.mines));
+    }
+    components
+}
+
+fn find_root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+/// Counts every valid mine layout of `component`, grouped by how many mines it uses.
+/// Returns `None` when the group is too large to enumerate.
+fn enumerate(component: &Component) -> Option<Vec<Tally>> {
+    let size = component.cells.len();
+    let mut cell_constraints: Vec<Vec<usize>> = vec![vec![]; size];
+    for (index, (cells, _)) in component.constraints.iter().enumerate() {
+        for cell in cells.iter() {
+            cell_constraints[*cell].push(index);
+        }
+    }
+    let mut placed = vec![0; component.constraints.len()];
+    let mut open: Vec<i32> = component
+        .constraints
+        .iter()
+        .map(|(cells, _)| cells.len() as i32)
+        .collect();
+    let mut assignment = vec![false; size];
+    let mut tallies: Vec<Tally> = vec![];
+    let mut steps = 0;
+    let finished = backtrack(
+        0,
+        component,
+        &cell_constraints,
+        &mut placed,
+        &mut open,
+        &mut assignment,
+        &mut tallies,
+        &mut steps,
+    );
+    finished.then_some(tallies)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn backtrack(
+    cell: usize,
+    component: &Component,
+    cell_constraints: &[Vec<usize>],
+    placed: &mut [i32],
+    open: &mut [i32],
+    assignment: &mut [bool],
+    tallies: &mut Vec<Tally>,
+    steps: &mut usize,
+) -> bool {
+    *steps += 1;
+    if *steps > MAX_ENUMERATION_STEPS {
+        return false;
+    }
+    if cell == assignment.len() {
+        let mines = assignment.iter().filter(|mine| **mine).count();
+        let tally = match tallies.iter_mut().find(|t| t.mines == mines) {
+            Some(tally) => tally,
+            None => {
+                tallies.push(Tally {
+                    mines,
+                    solutions: 0.0,
+                    cell_mines: vec![0.0; assignment.len()],
+                });
+                tallies.last_mut().unwrap()
+            }
+        };
+        tally.solutions += 1.0;
+        for (i, mine) in assignment.iter().enumerate() {
+            if *mine {
+                tally.cell_mines[i] += 1.0;
+            }
+        }
+        return true;
+    }
+    for mine in [false, true] {
+        let mut valid = true;
+        for constraint in cell_constraints[cell].iter() {
+            open[*constraint] -= 1;
+            placed[*constraint] += mine as i32;
+            let target = component.constraints[*constraint].1;
+            if placed[*constraint] > target || placed[*constraint] + open[*constraint] < target {
+                valid = false;
+            }
+        }
+        assignment[cell] = mine;
+        if valid
+            && !backtrack(
+                cell + 1,
+                component,
+                cell_constraints,
+                placed,
+                open,
+                assignment,
+                tallies,
+                steps,
+            )
+        {
+            return false;
+        }
+        for constraint in cell_constraints[cell].iter() {
+            open[*constraint] += 1;
+            placed[*constraint] -= mine as i32;
+        }
+    }
+    assignment[cell] = false;
+    true
+}
+
+/// Plays `game` using deductions only. Returns whether it was won without guessing
+/// before `deadline`.
+pub(crate) fn solves(game: &mut Game, deadline: Option<Instant>) -> bool {
+    while game.status() == GameStatus::Playing {
+        let Some(deductions) = deduce_until(game.board(), game.settings(), deadline) else {
+            return false;
+        };
+        if deductions.is_empty() {
+            break;
+        }
+        for pos in deductions.mines {
+            game.toggle_flag(pos);
+        }
+        for pos in deductions.safe {
+            game.reveal(pos);
+        }
+    }
+    game.status() == GameStatus::Won
+}
+
+fn get_constraints(board: &Board, deadline: Option<Instant>) -> Option<Vec<Constraint>> {
+    let mut constraints = vec![];
+    let mut seen = HashSet::new();
+    for (row_number, row) in board.rows().enumerate() {
+        if timed_out(deadline) {
+            return None;
+        }
+        for (column_number, cell) in row.iter().enumerate() {
+            if cell.hidden || cell.is_flagged() || cell.element == 'M' {
+                continue;
+            }
+            let number = cell.element.to_digit(10).unwrap_or(0) as i32;
+            let mut cells = vec![];
+            let mut flagged = 0;
+            for (y, x) in board.neighbours(row_number, column_number) {
+                let around = board[y][x];
+                if around.is_flagged() {
+                    flagged += 1;
+                } else if around.hidden {
+                    cells.push((y, x));
+                }
+            }
+            if cells.is_empty() {
+                continue;
+            }
+            let constraint = Constraint {
+                cells,
+                mines: number - flagged,
+            };
+            if seen.insert(constraint.clone()) {
+                constraints.push(constraint);
+            }
+        }
+    }
+    Some(constraints)
+}
+
+fn count_unknown(board: &Board) -> (Vec<(usize, usize)>, i32) {
+    let mut unknown = vec![];
+    let mut flagged = 0;
+    for (row_number, row) in board.rows().enumerate() {
+        for (column_number, cell) in row.iter().enumerate() {
+            if cell.is_flagged() {
+                flagged += 1;
+            } else if cell.hidden {
+                unknown.push((row_number, column_number));
+            }
+        }
+    }
+    (unknown, flagged)
+}
+
+fn to_positions(mut cells: Vec<(usize, usize)>) -> Vec<CellPos> {
+    cells.sort_unstable();
+    cells.dedup();
+    cells
+        .into_iter()
+        .map(|(y, x)| CellPos {
+            x: x as i32,
+            y: y as i32,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_board;
+
+    fn pos(x: i32, y: i32) -> CellPos {
+        CellPos { x, y }
+    }
+
+    #[test]
+    fn deduce_trusts_flags() {
+        let board = parse_board("F1.\n").unwrap();
+        let deductions = deduce(&board, &Settings::new(3, 1, 1));
+        assert_eq!(deductions.safe, vec![pos(2, 0)]);
+        assert!(deductions.mines.is_empty());
+    }
+
+    #[test]
+    fn deduce_uses_subsets() {
+        let board = parse_board(".*.*.\n11211\n00000\n").unwrap();
+        let deductions = deduce(&board, &Settings::new(5, 3, 2));
+        assert_eq!(deductions.safe, vec![pos(2, 0)]);
+        assert!(deductions.mines.is_empty());
+    }
+
+    #[test]
+    fn solves_one_two_one() {
+        let board = parse_board(".*.*.\n11211\n00000\n").unwrap();
+        let mut game = Game::from_board(board, Settings::new(5, 3, 2));
+        assert!(solves(&mut game, None));
+        assert_eq!(game.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn solves_gives_up_on_a_guess() {
+        let board = parse_board(".*\n11\n").unwrap();
+        let mut game = Game::from_board(board, Settings::new(2, 2, 1));
+        assert!(!solves(&mut game, None));
+    }
+
+    #[test]
+    fn deduce_finds_nothing_in_a_fifty_fifty() {
+        let board = parse_board(".*\n11\n").unwrap();
+        assert!(deduce(&board, &Settings::new(2, 2, 1)).is_empty());
+    }
+
+    #[test]
+    fn mine_probabilities_of_a_fifty_fifty() {
+        let board = parse_board(".*\n11\n").unwrap();
+        let probabilities = mine_probabilities(&board, &Settings::new(2, 2, 1)).unwrap();
+        assert_eq!(probabilities, vec![vec![0.5, 0.5], vec![0.0, 0.0]]);
+    }
+
+    #[test]
+    fn mine_probabilities_spread_the_rest_evenly() {
+        let board = parse_board("F1..\n").unwrap();
+        let probabilities = mine_probabilities(&board, &Settings::new(4, 1, 2)).unwrap();
+        assert_eq!(probabilities, vec![vec![1.0, 0.0, 0.0, 1.0]]);
+        let board = parse_board("F1...\n").unwrap();
+        let probabilities = mine_probabilities(&board, &Settings::new(5, 1, 2)).unwrap();
+        assert_eq!(probabilities, vec![vec![1.0, 0.0, 0.0, 0.5, 0.5]]);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{data_path, game::Game, load_toml, save_toml, GameStatus, Metrics, Settings};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io, time::Duration};
+
+const STATS_FILE: &str = "stats.toml";
+/// How many best times are kept for each board.
+const BEST_TIMES_KEPT: usize = 5;
+
+/// Results for one board size, keyed by [`board_name`] in [`Stats`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoardStats {
+    pub games_played: u32,
+    pub games_won: u32,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Fastest wins in milliseconds, fastest first.
+    pub best_times: Vec<u64>,
+    /// Best of each [`Metrics`] rate over the wins that count for best times. Each can come
+    /// from a different game.
+    #[serde(default)]
+    pub best_three_bv_per_second: f64,
+    #[serde(default)]
+    pub best_ioe: f64,
+    #[serde(default)]
+    pub best_click_efficiency: f64,
+}
+impl BoardStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.games_played == 0 {
+            0.0
+        } else {
+            self.games_won as f64 / self.games_played as f64
+        }
+    }
+}
+
+/// Every finished game, stored in `stats.toml` under the user's data directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub boards: BTreeMap<String, BoardStats>,
+}
+impl Stats {
+    /// Reads the stats file. A missing file is not an error and gives empty stats.
+    pub fn load() -> io::Result<Stats> {
+        Ok(load_toml(&data_path(STATS_FILE)?)?.unwrap_or_default())
+    }
+    pub fn save(&self) -> io::Result<()> {
+        save_toml(&data_path(STATS_FILE)?, self)
+    }
+    /// Adds a finished game. Returns true when it set a new best time.
+    ///
+    /// Games that used hints or undo still count as played and won, but not towards best
+    /// times.
+    pub fn record(&mut self, game: &Game, metrics: &Metrics) -> bool {
+        let board = self.boards.entry(board_name(game.settings())).or_default();
+        board.games_played += 1;
+        if game.status() != GameStatus::Won {
+            board.current_streak = 0;
+            return false;
+        }
+        board.games_won += 1;
+        board.current_streak += 1;
+        board.best_streak = board.best_streak.max(board.current_streak);
+        if game.hints_used() > 0 || game.is_practice() {
+            return false;
+        }
+        board.best_three_bv_per_second = board
+            .best_three_bv_per_second
+            .max(metrics.three_bv_per_second());
+        board.best_ioe = board.best_ioe.max(metrics.ioe());
+        board.best_click_efficiency = board.best_click_efficiency.max(metrics.click_efficiency());
+        let time = game.elapsed().as_millis() as u64;
+        board.best_times.push(time);
+        board.best_times.sort_unstable();
+        board.best_times.truncate(BEST_TIMES_KEPT);
+        board.best_times.first() == Some(&time)
+    }
+}
+
+/// Names the presets, and describes any other size as a custom board.
+pub fn board_name(settings: &Settings) -> String {
+    match (settings.width, settings.height, settings.mines) {
+        (8, 8, 10) => "Easy".to_owned(),
+        (16, 16, 40) => "Normal".to_owned(),
+        (30, 16, 99) => "Hard".to_owned(),
+        (width, height, mines) => format!("Custom {width}x{height}, {mines} mines"),
+    }
+}
+
+pub fn format_time(millis: u64) -> String {
+    format!("{:.3}s", Duration::from_millis(millis).as_secs_f64())
+}
@@ -0,0 +1,217 @@
+//! The scripted lessons of the tutorial. Each lesson is a small board in the text format
+//! (see [`crate::parse_board`]) and the moves the player is walked through on it.
+use crate::{game::Game, parse_board, CellPos, Mark, Move, MoveKind, Settings};
+
+/// One move the player is asked to make.
+pub(crate) struct Step {
+    pub(crate) instruction: &'static str,
+    pub(crate) kind: MoveKind,
+    /// The cells where the move is accepted, as `(x, y)`.
+    pub(crate) cells: &'static [(i32, i32)],
+    /// Whether the cells are marked on the board from the start. Otherwise they are only
+    /// shown after a wrong move.
+    pub(crate) marked: bool,
+}
+
+pub(crate) struct Lesson {
+    pub(crate) title: &'static str,
+    pub(crate) board: &'static str,
+    pub(crate) intro: &'static [&'static str],
+    pub(crate) steps: &'static [Step],
+    pub(crate) outro: &'static [&'static str],
+}
+impl Lesson {
+    /// A new game on the lesson's board, with its size from the board and everything else
+    /// from `settings`.
+    pub(crate) fn game(&self, mut settings: Settings) -> Game {
+        let board = parse_board(self.board).expect("tutorial boards are valid");
+        settings.width = board.width() as i32;
+        settings.height = board.height() as i32;
+        settings.mines = board.cells().filter(|cell| cell.element == 'M').count() as i32;
+        settings.seed = None;
+        settings.daily = None;
+        settings.puzzle = None;
+        settings.tutorial = true;
+        Game::from_board(board, settings)
+    }
+}
+impl Step {
+    pub(crate) fn cells(&self) -> impl Iterator<Item = CellPos> {
+        self.cells.iter().map(|&(x, y)| CellPos { x, y })
+    }
+    /// Whether `mv`, just made in `game`, is the one asked for. A flag has to be placed,
+    /// not taken off.
+    pub(crate) fn accepts(&self, mv: &Move, game: &Game) -> bool {
+        mv.kind == self.kind
+            && self.cells().any(|pos| pos == mv.pos)
+            && (mv.kind != MoveKind::Flag
+                || game.board()[mv.pos.y as usize][mv.pos.x as usize].mark() == Mark::Flag)
+    }
+}
+
+pub(crate) const LESSONS: [Lesson; 6] = [
+    Lesson {
+        title: "Revealing",
+        board: "
+.....
+.*...
+.....
+...*.
+.....
+",
+        intro: &[
+            "Every cell hides either a mine or a number.",
+            "Revealing a mine loses the game. The marked cell is safe.",
+        ],
+        steps: &[Step {
+            instruction: "Reveal the marked cell.",
+            kind: MoveKind::Reveal,
+            cells: &[(2, 2)],
+            marked: true,
+        }],
+        outro: &[
+            "The 2 means that two of the eight cells around it are mines.",
+            "Numbers are all you get to work out where the mines are.",
+        ],
+    },
+    Lesson {
+        title: "Flood fill",
+        board: "
+......
+......
+......
+....*.
+.....*
+",
+        intro: &[
+            "A cell with no mines around it shows no number.",
+            "Its neighbours are all safe, so the game reveals them for you.",
+        ],
+        steps: &[Step {
+            instruction: "Reveal the marked corner.",
+            kind: MoveKind::Reveal,
+            cells: &[(0, 0)],
+            marked: true,
+        }],
+        outro: &[
+            "The opening spread until it reached cells next to mines.",
+            "Openings are the quickest way to clear a board.",
+        ],
+    },
+    Lesson {
+        title: "Flagging",
+        board: "
+*1001.
+11001*
+00001.
+",
+        intro: &[
+            "The 1s next to the hidden corner touch no other hidden cell.",
+            "So that corner must be their mine.",
+        ],
+        steps: &[Step {
+            instruction: "Flag the marked cell. Flagging it again takes the flag off.",
+            kind: MoveKind::Flag,
+            cells: &[(0, 0)],
+            marked: true,
+        }],
+        outro: &[
+            "Flags keep you from revealing a mine by accident.",
+            "The flag counter on the status line shows how many mines are left.",
+        ],
+    },
+    Lesson {
+        title: "Chording",
+        board: "
+F1..
+11..
+....
+...*
+",
+        intro: &[
+            "The marked 1 already has a flag next to it, which is its mine.",
+            "So its other hidden neighbours are safe.",
+        ],
+        steps: &[Step {
+            instruction: "Chord the marked 1 to reveal all of them at once.",
+            kind: MoveKind::Chord,
+            cells: &[(1, 1)],
+            marked: true,
+        }],
+        outro: &[
+            "A chord works once a number has as many flags around it as it shows.",
+            "A wrong flag makes it reveal a mine, so only chord what you are sure of.",
+        ],
+    },
+    Lesson {
+        title: "The 1-2-1 pattern",
+        board: "
+.*.*.
+11211
+00000
+",
+        intro: &[
+            "Look at the 1-2-1 in the middle of the row of numbers.",
+            "A mine above the 2 would be the only mine of both 1s,",
+            "leaving the 2 one short. So its mines are above the 1s.",
+        ],
+        steps: &[
+            Step {
+                instruction: "Flag the cell above the left 1 of the 1-2-1.",
+                kind: MoveKind::Flag,
+                cells: &[(1, 0)],
+                marked: false,
+            },
+            Step {
+                instruction: "Flag the cell above the right 1 of the 1-2-1.",
+                kind: MoveKind::Flag,
+                cells: &[(3, 0)],
+                marked: false,
+            },
+            Step {
+                instruction: "The cell above the 2 is safe. Reveal it.",
+                kind: MoveKind::Reveal,
+                cells: &[(2, 0)],
+                marked: false,
+            },
+        ],
+        outro: &["Whenever you see 1-2-1 along a wall of hidden cells, the mines face the 1s."],
+    },
+    Lesson {
+        title: "The 1-2-2-1 pattern",
+        board: "
+.**.
+1221
+0000
+",
+        intro: &[
+            "Now the numbers read 1-2-2-1.",
+            "Each 2 can have only one mine next to its 1,",
+            "so its other mine is above the other 2.",
+        ],
+        steps: &[
+            Step {
+                instruction: "Flag the cell above the left 2.",
+                kind: MoveKind::Flag,
+                cells: &[(1, 0)],
+                marked: false,
+            },
+            Step {
+                instruction: "Flag the cell above the right 2.",
+                kind: MoveKind::Flag,
+                cells: &[(2, 0)],
+                marked: false,
+            },
+            Step {
+                instruction: "The cells above the 1s are safe. Reveal one of them.",
+                kind: MoveKind::Reveal,
+                cells: &[(0, 0), (3, 0)],
+                marked: false,
+            },
+        ],
+        outro: &[
+            "In 1-2-2-1 the mines face the 2s.",
+            "That's the tutorial. Have fun sweeping!",
+        ],
+    },
+];